@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS compounds (
+    compound_id bigserial PRIMARY KEY,
+    entry_id text NOT NULL REFERENCES entries ON DELETE CASCADE,
+    name text NOT NULL,
+    synonyms text[] NOT NULL,
+    formula text,
+    mol_mass double precision,
+    structure text,
+    activities text[] NOT NULL,
+    targets text[] NOT NULL,
+    database_ids text[] NOT NULL,
+    evidences text[] NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS compounds_entry_id_idx ON compounds (entry_id);
+CREATE INDEX IF NOT EXISTS compounds_formula_idx ON compounds (formula);
+CREATE INDEX IF NOT EXISTS compounds_mol_mass_idx ON compounds (mol_mass);
+CREATE INDEX IF NOT EXISTS compounds_activities_idx ON compounds USING GIN (activities);
@@ -0,0 +1,16 @@
+-- Reserved accessions can't be recreated, so refuse to roll back rather than drop them
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM entries WHERE tax_id IS NULL) THEN
+        RAISE EXCEPTION 'entries without a taxon exist, publish or delete the reserved entries first';
+    END IF;
+END
+$$;
+
+ALTER TABLE submission_requests DROP COLUMN IF EXISTS resolved_at;
+ALTER TABLE submission_requests DROP COLUMN IF EXISTS resolved_by;
+ALTER TABLE submission_requests DROP COLUMN IF EXISTS entry_id;
+ALTER TABLE submission_requests DROP COLUMN IF EXISTS status;
+
+ALTER TABLE entries DROP COLUMN IF EXISTS status;
+ALTER TABLE entries ALTER COLUMN tax_id SET NOT NULL;
@@ -0,0 +1,8 @@
+-- Reserved entries don't have any data yet, so they can't have a taxon either
+ALTER TABLE entries ALTER COLUMN tax_id DROP NOT NULL;
+ALTER TABLE entries ADD COLUMN IF NOT EXISTS status text NOT NULL DEFAULT 'published';
+
+ALTER TABLE submission_requests ADD COLUMN IF NOT EXISTS status text NOT NULL DEFAULT 'pending';
+ALTER TABLE submission_requests ADD COLUMN IF NOT EXISTS entry_id text REFERENCES entries ON DELETE SET NULL;
+ALTER TABLE submission_requests ADD COLUMN IF NOT EXISTS resolved_by text REFERENCES submitters ON DELETE SET NULL;
+ALTER TABLE submission_requests ADD COLUMN IF NOT EXISTS resolved_at timestamp(0) with time zone;
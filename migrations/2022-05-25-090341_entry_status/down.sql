@@ -0,0 +1 @@
+ALTER TABLE entries DROP CONSTRAINT IF EXISTS entries_status_check;
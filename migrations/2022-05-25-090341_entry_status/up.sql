@@ -0,0 +1,2 @@
+ALTER TABLE entries ADD CONSTRAINT entries_status_check
+    CHECK (status IN ('published', 'retired', 'embargoed', 'reserved'));
@@ -0,0 +1 @@
+ALTER TABLE submission_requests DROP COLUMN IF EXISTS resolution_reason;
@@ -0,0 +1 @@
+ALTER TABLE submission_requests ADD COLUMN IF NOT EXISTS resolution_reason text;
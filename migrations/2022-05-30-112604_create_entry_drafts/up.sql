@@ -0,0 +1,10 @@
+-- Drafts of new or updated entries, kept apart from the published data
+CREATE TABLE IF NOT EXISTS submissions (
+    submission_id bigserial PRIMARY KEY,
+    entry_id text NOT NULL REFERENCES entries ON DELETE CASCADE,
+    user_id text NOT NULL REFERENCES submitters ON DELETE CASCADE,
+    data jsonb NOT NULL,
+    created timestamp(0) with time zone NOT NULL DEFAULT now(),
+    updated timestamp(0) with time zone NOT NULL DEFAULT now(),
+    UNIQUE (entry_id, user_id)
+);
@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS entry_revisions (
+    revision_id bigserial PRIMARY KEY,
+    entry_id text NOT NULL REFERENCES entries ON DELETE CASCADE,
+    revision int NOT NULL,
+    version text NOT NULL,
+    contributors text[] NOT NULL DEFAULT '{}',
+    comments text[] NOT NULL DEFAULT '{}',
+    data jsonb NOT NULL,
+    created timestamp(0) with time zone NOT NULL DEFAULT now(),
+    UNIQUE (entry_id, revision)
+);
+
+-- Entries imported so far become the first revision, with their latest changelog record
+INSERT INTO entry_revisions (entry_id, revision, version, contributors, comments, data)
+SELECT
+    id,
+    1,
+    coalesce(data->'changelog'->-1->>'version', ''),
+    ARRAY(SELECT jsonb_array_elements_text(coalesce(data->'changelog'->-1->'contributors', '[]'))),
+    ARRAY(SELECT jsonb_array_elements_text(coalesce(data->'changelog'->-1->'comments', '[]'))),
+    data
+FROM entries
+WHERE data IS NOT NULL;
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS entry_search (
+    entry_id text PRIMARY KEY REFERENCES entries ON DELETE CASCADE,
+    document tsvector NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS entry_search_document_idx ON entry_search USING GIN (document);
+
+-- Index the entries imported so far, the same fields the importer picks up
+INSERT INTO entry_search (entry_id, document)
+SELECT
+    id,
+    to_tsvector('simple', concat_ws(' ',
+        (SELECT string_agg(v #>> '{}', ' ') FROM jsonb_path_query(data, '$.cluster.compounds[*].compound') v),
+        (SELECT string_agg(v #>> '{}', ' ') FROM jsonb_path_query(data, '$.cluster.compounds[*].chem_synonyms[*]') v),
+        organism_name,
+        (SELECT string_agg(v #>> '{}', ' ') FROM jsonb_path_query(data, '$.cluster.genes.annotations[*].product') v),
+        (SELECT string_agg(v #>> '{}', ' ') FROM jsonb_path_query(data, '$.cluster.publications[*]') v)
+    ))
+FROM entries
+WHERE data IS NOT NULL;
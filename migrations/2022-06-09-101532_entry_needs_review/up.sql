@@ -0,0 +1,2 @@
+-- Entries imported with an unknown taxid get a placeholder lineage until a curator fixes them
+ALTER TABLE entries ADD COLUMN IF NOT EXISTS needs_review boolean NOT NULL DEFAULT false;
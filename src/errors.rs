@@ -17,6 +17,9 @@ pub enum MibigError {
     DatabaseError(diesel::result::Error),
     Unauthorised,
     MissingToken,
+    InvalidRole(String),
+    InvalidInput(String),
+    InvalidQuery(String),
 }
 
 macro_rules! implement_custom_error_from {
@@ -43,6 +46,9 @@ impl fmt::Display for MibigError {
             MibigError::DatabaseError(ref err) => write!(f, "Database error: {}", err),
             MibigError::Unauthorised => write!(f, "Unauthorised"),
             MibigError::MissingToken => write!(f, "No authentication token"),
+            MibigError::InvalidRole(ref err) => write!(f, "Invalid role: {}", err),
+            MibigError::InvalidInput(ref err) => write!(f, "Invalid input: {}", err),
+            MibigError::InvalidQuery(ref err) => write!(f, "Invalid query: {}", err),
         }
     }
 }
@@ -56,7 +62,10 @@ impl error::Error for MibigError {
             MibigError::NotImplemented
             | MibigError::InvalidTaxID(_)
             | MibigError::Unauthorised
-            | MibigError::MissingToken => None,
+            | MibigError::MissingToken
+            | MibigError::InvalidRole(_)
+            | MibigError::InvalidInput(_)
+            | MibigError::InvalidQuery(_) => None,
         }
     }
 }
@@ -64,6 +73,16 @@ impl error::Error for MibigError {
 impl<'r> Responder<'r, 'static> for MibigError {
     fn respond_to(self, _: &'r Request<'_>) -> Result<'static> {
         match self {
+            MibigError::DatabaseError(diesel::result::Error::NotFound) => {
+                let body = "Not found".to_string();
+
+                let res = Response::build()
+                    .status(Status::NotFound)
+                    .header(ContentType::Plain)
+                    .sized_body(body.len(), Cursor::new(body))
+                    .finalize();
+                return Ok(res);
+            }
             MibigError::DatabaseError(_)
             | MibigError::InvalidTaxID(_)
             | MibigError::Io(_)
@@ -87,7 +106,10 @@ impl<'r> Responder<'r, 'static> for MibigError {
                     .finalize();
                 return Ok(res);
             }
-            MibigError::MissingToken => {
+            MibigError::MissingToken
+            | MibigError::InvalidRole(_)
+            | MibigError::InvalidInput(_)
+            | MibigError::InvalidQuery(_) => {
                 let body = format!("{}", self);
 
                 let res = Response::build()
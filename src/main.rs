@@ -15,6 +15,8 @@ mod repo;
 mod req;
 #[allow(unused_imports)]
 mod schema;
+mod submission;
+mod taxa;
 mod token;
 mod user;
 mod utils;
@@ -47,6 +49,10 @@ enum Subcommand {
     Req(req::ReqOpts),
     #[structopt(name = "token", about = "Manage tokens")]
     Token(token::TokenOpts),
+    #[structopt(name = "submission", about = "Manage draft submissions")]
+    Submission(submission::SubmissionOpts),
+    #[structopt(name = "taxa", about = "Manage taxonomy data")]
+    Taxa(taxa::TaxaOpts),
 }
 
 mod web;
@@ -63,6 +69,7 @@ fn rocket() -> rocket::Rocket<rocket::Build> {
 
     rocket::custom(figment)
         .mount("/api/v1", web::routes::get_routes())
+        .manage(utils::mailer::from_env())
         .attach(DBPool::fairing())
 }
 
@@ -78,5 +85,7 @@ async fn main() {
         Subcommand::User(cfg) => user::user(cfg),
         Subcommand::Req(cfg) => req::req(cfg),
         Subcommand::Token(cfg) => token::token(cfg),
+        Subcommand::Submission(cfg) => submission::submission(cfg),
+        Subcommand::Taxa(cfg) => taxa::taxa(cfg),
     };
 }
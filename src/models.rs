@@ -1,2 +1,3 @@
 pub mod db;
 pub mod legacy_schema;
+pub mod query;
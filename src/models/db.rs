@@ -1,30 +1,15 @@
-use crate::schema::*;
-
+pub mod bgc_types;
+pub mod compounds;
+pub mod entries;
+pub mod entry_revisions;
+pub mod entry_search;
+pub mod roles;
+pub mod stats;
+pub mod submission;
 pub mod submission_requests;
 pub mod submitters;
 pub mod taxa;
 pub mod tokens;
 
+pub use entries::*;
 pub use taxa::*;
-
-#[derive(Queryable, Identifiable, Associations, PartialEq, Debug)]
-#[table_name = "entries"]
-pub struct Entry {
-    pub id: String,
-    pub minimal: bool,
-    pub tax_id: i64,
-    pub organism_name: String,
-    pub biosyn_class: Vec<String>,
-    pub legacy_comment: Option<String>,
-}
-
-#[derive(Insertable)]
-#[table_name = "entries"]
-pub struct NewEntry {
-    pub id: String,
-    pub minimal: bool,
-    pub tax_id: i64,
-    pub organism_name: String,
-    pub biosyn_class: Vec<String>,
-    pub legacy_comment: Option<String>,
-}
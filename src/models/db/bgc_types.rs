@@ -0,0 +1,297 @@
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::errors::MibigError;
+use crate::models::legacy_schema;
+use crate::schema::bgc_types::dsl::bgc_types as all_bgc_types;
+use crate::schema::rel_entries_types::dsl::rel_entries_types as all_rel_entries_types;
+use crate::schema::{bgc_types, entries, rel_entries_types};
+
+#[derive(Queryable, Identifiable, Serialize, PartialEq, Debug, Clone)]
+#[table_name = "bgc_types"]
+#[primary_key(bgc_type_id)]
+pub struct BgcType {
+    #[serde(skip_serializing)]
+    pub bgc_type_id: i32,
+    pub term: String,
+    pub name: String,
+    pub description: String,
+    #[serde(skip_serializing)]
+    pub parent_id: Option<i32>,
+    pub safe_class: String,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "rel_entries_types"]
+pub struct NewEntryType {
+    pub entry_id: String,
+    pub bgc_type_id: i32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BgcTypeNode {
+    #[serde(flatten)]
+    pub bgc_type: BgcType,
+    pub children: Vec<BgcTypeNode>,
+}
+
+/// Lowercase and drop punctuation so "Ca+-dependent lipopeptide" and the
+/// MIBiG JSON spelling of the same subclass compare equal
+fn normalise(value: &str) -> String {
+    value
+        .to_lowercase()
+        .replace(['-', '_'], " ")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+impl BgcType {
+    pub fn all(conn: &PgConnection) -> Result<Vec<BgcType>, MibigError> {
+        let res = all_bgc_types
+            .order(bgc_types::bgc_type_id)
+            .load::<BgcType>(conn)?;
+        Ok(res)
+    }
+
+    pub fn by_term(term: &str, conn: &PgConnection) -> Result<BgcType, MibigError> {
+        let bgc_type = all_bgc_types.filter(bgc_types::term.eq(term)).first(conn)?;
+        Ok(bgc_type)
+    }
+
+    /// IDs of this type and every type below it in the hierarchy
+    pub fn descendant_ids(&self, types: &[BgcType]) -> Vec<i32> {
+        let mut ids = vec![self.bgc_type_id];
+        let mut idx = 0;
+        while idx < ids.len() {
+            let current = ids[idx];
+            ids.extend(
+                types
+                    .iter()
+                    .filter(|t| t.parent_id == Some(current))
+                    .map(|t| t.bgc_type_id),
+            );
+            idx += 1;
+        }
+        ids
+    }
+
+    pub fn tree(types: &[BgcType]) -> Vec<BgcTypeNode> {
+        fn children_of(parent_id: Option<i32>, types: &[BgcType]) -> Vec<BgcTypeNode> {
+            types
+                .iter()
+                .filter(|t| t.parent_id == parent_id)
+                .map(|t| BgcTypeNode {
+                    bgc_type: t.clone(),
+                    children: children_of(Some(t.bgc_type_id), types),
+                })
+                .collect()
+        }
+        children_of(None, types)
+    }
+
+    /// Map the biosynthetic classes and subclasses of an entry to `bgc_types` IDs.
+    ///
+    /// Returns the matched IDs and the subclass names that could not be matched.
+    pub fn ids_for_entry(
+        cluster: &legacy_schema::Cluster,
+        types: &[BgcType],
+    ) -> (Vec<i32>, Vec<String>) {
+        let mut ids: Vec<i32> = Vec::new();
+        let mut unmatched: Vec<String> = Vec::new();
+
+        for class in cluster.biosyn_class.iter() {
+            let class_type = match types.iter().find(|t| t.term == class.term()) {
+                Some(t) => t,
+                None => continue,
+            };
+            ids.push(class_type.bgc_type_id);
+
+            let candidates = class_type.descendant_ids(types);
+            let class_suffix = normalise(&class_type.name);
+
+            for subclass in cluster.subclasses(*class) {
+                let wanted = normalise(&subclass);
+                let found = types.iter().find(|t| {
+                    t.bgc_type_id != class_type.bgc_type_id
+                        && candidates.contains(&t.bgc_type_id)
+                        && (normalise(&t.name) == wanted
+                            || normalise(&t.term) == wanted
+                            || normalise(&t.name) == format!("{} {}", wanted, class_suffix))
+                });
+                match found {
+                    Some(t) => ids.push(t.bgc_type_id),
+                    None => unmatched.push(subclass),
+                }
+            }
+        }
+
+        ids.sort_unstable();
+        ids.dedup();
+        (ids, unmatched)
+    }
+
+    pub fn set_for_entry(
+        entry_id: &str,
+        type_ids: &[i32],
+        conn: &PgConnection,
+    ) -> Result<(), MibigError> {
+        diesel::delete(all_rel_entries_types.filter(rel_entries_types::entry_id.eq(entry_id)))
+            .execute(conn)?;
+        let new_types: Vec<NewEntryType> = type_ids
+            .iter()
+            .map(|id| NewEntryType {
+                entry_id: entry_id.to_string(),
+                bgc_type_id: *id,
+            })
+            .collect();
+        diesel::insert_into(rel_entries_types::table)
+            .values(&new_types)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Accessions of all entries of this type, including its subtypes
+    pub fn entry_ids(
+        &self,
+        visible: &[&str],
+        conn: &PgConnection,
+    ) -> Result<Vec<String>, MibigError> {
+        let types = BgcType::all(conn)?;
+        let res = all_rel_entries_types
+            .inner_join(entries::table)
+            .filter(rel_entries_types::bgc_type_id.eq_any(self.descendant_ids(&types)))
+            .filter(entries::status.eq_any(visible))
+            .select(rel_entries_types::entry_id)
+            .distinct()
+            .order(rel_entries_types::entry_id)
+            .load::<String>(conn)?;
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn bgc_type(id: i32, term: &str, name: &str, parent_id: Option<i32>) -> BgcType {
+        BgcType {
+            bgc_type_id: id,
+            term: term.to_string(),
+            name: name.to_string(),
+            description: String::new(),
+            parent_id,
+            safe_class: String::new(),
+        }
+    }
+
+    fn types() -> Vec<BgcType> {
+        vec![
+            bgc_type(1, "nrps", "NRP", None),
+            bgc_type(2, "lipopeptide", "Lipopeptide", Some(1)),
+            bgc_type(
+                3,
+                "ca_dependent_lipopeptide",
+                "Ca+-dependent lipopeptide",
+                Some(2),
+            ),
+            bgc_type(4, "pks", "Polyketide", None),
+            bgc_type(5, "modular_type_i", "Modular type I polyketide", Some(4)),
+            bgc_type(6, "terpene", "Terpene", None),
+        ]
+    }
+
+    fn cluster_with(
+        classes: serde_json::Value,
+        blocks: serde_json::Value,
+    ) -> legacy_schema::Cluster {
+        let mut value = json!({
+            "biosyn_class": classes,
+            "compounds": [],
+            "loci": {"accession": "AB000001", "completeness": "complete"},
+            "mibig_accession": "BGC0000001",
+            "minimal": true,
+            "ncbi_tax_id": "1909",
+            "organism_name": "Streptomyces roseosporus",
+            "publications": [],
+        });
+        for (key, block) in blocks.as_object().unwrap() {
+            value[key] = block.clone();
+        }
+        serde_json::from_value(value).expect("test cluster should parse")
+    }
+
+    #[test]
+    fn normalise_ignores_case_and_punctuation() {
+        assert_eq!(
+            normalise("Ca+-dependent  Lipopeptide"),
+            "ca+ dependent lipopeptide"
+        );
+        assert_eq!(normalise("modular_type_I"), "modular type i");
+    }
+
+    #[test]
+    fn descendant_ids_walks_the_whole_subtree() {
+        let types = types();
+        assert_eq!(types[0].descendant_ids(&types), vec![1, 2, 3]);
+        assert_eq!(types[3].descendant_ids(&types), vec![4, 5]);
+        assert_eq!(types[2].descendant_ids(&types), vec![3]);
+    }
+
+    #[test]
+    fn exact_subclass_names_match() {
+        let cluster = cluster_with(
+            json!(["NRP"]),
+            json!({"nrp": {"subclass": "Ca+-dependent lipopeptide"}}),
+        );
+        assert_eq!(
+            BgcType::ids_for_entry(&cluster, &types()),
+            (vec![1, 3], Vec::<String>::new())
+        );
+    }
+
+    #[test]
+    fn normalised_subclass_names_match() {
+        // By term, and by name without the class suffix
+        let cluster = cluster_with(
+            json!(["NRP", "Polyketide"]),
+            json!({
+                "nrp": {"subclass": "Ca-dependent_lipopeptide"},
+                "polyketide": {"subclasses": ["modular type I"]},
+            }),
+        );
+        assert_eq!(
+            BgcType::ids_for_entry(&cluster, &types()),
+            (vec![1, 3, 4, 5], Vec::<String>::new())
+        );
+
+        let cluster = cluster_with(
+            json!(["NRP"]),
+            json!({"nrp": {"subclass": "CA_DEPENDENT_LIPOPEPTIDE"}}),
+        );
+        assert_eq!(
+            BgcType::ids_for_entry(&cluster, &types()),
+            (vec![1, 3], Vec::<String>::new())
+        );
+    }
+
+    #[test]
+    fn unmatched_subclasses_are_reported() {
+        // Subclasses only match below their own class
+        let cluster = cluster_with(
+            json!(["Polyketide", "Terpene"]),
+            json!({"polyketide": {"subclasses": ["Lipopeptide", "Enediyne"]}}),
+        );
+        assert_eq!(
+            BgcType::ids_for_entry(&cluster, &types()),
+            (
+                vec![4, 6],
+                vec!["Enediyne".to_string(), "Lipopeptide".to_string()]
+            )
+        );
+    }
+}
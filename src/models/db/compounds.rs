@@ -0,0 +1,163 @@
+use diesel;
+use diesel::pg::{Pg, PgConnection};
+use diesel::prelude::*;
+use diesel::sql_types::{Array, Text};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::errors::MibigError;
+use crate::models::legacy_schema;
+use crate::schema::{compounds, entries};
+use crate::schema::compounds::dsl::compounds as all_compounds;
+
+sql_function!(fn array_to_string(array: Array<Text>, delimiter: Text) -> Text);
+
+#[derive(Queryable, Identifiable, Serialize, PartialEq, Debug)]
+#[table_name = "compounds"]
+#[primary_key(compound_id)]
+pub struct Compound {
+    #[serde(skip_serializing)]
+    pub compound_id: i64,
+    pub entry_id: String,
+    pub name: String,
+    pub synonyms: Vec<String>,
+    pub formula: Option<String>,
+    pub mol_mass: Option<f64>,
+    pub structure: Option<String>,
+    pub activities: Vec<String>,
+    pub targets: Vec<String>,
+    pub database_ids: Vec<String>,
+    pub evidences: Vec<String>,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "compounds"]
+pub struct NewCompound {
+    pub entry_id: String,
+    pub name: String,
+    pub synonyms: Vec<String>,
+    pub formula: Option<String>,
+    pub mol_mass: Option<f64>,
+    pub structure: Option<String>,
+    pub activities: Vec<String>,
+    pub targets: Vec<String>,
+    pub database_ids: Vec<String>,
+    pub evidences: Vec<String>,
+}
+
+impl NewCompound {
+    pub fn from_legacy(entry_id: &str, compound: &legacy_schema::Compound) -> NewCompound {
+        NewCompound {
+            entry_id: entry_id.to_string(),
+            name: compound.name.to_owned(),
+            synonyms: compound.synonyms.to_owned().unwrap_or_default(),
+            formula: compound.molecular_formula.to_owned(),
+            mol_mass: compound.mol_mass,
+            structure: compound.structure.to_owned(),
+            activities: compound.activities.to_owned().unwrap_or_default(),
+            targets: compound
+                .targets
+                .iter()
+                .flatten()
+                .map(|target| target.name.to_owned())
+                .collect(),
+            database_ids: as_strings(&compound.database_ids),
+            evidences: as_strings(&compound.evidences),
+        }
+    }
+}
+
+/// Flatten a list of values that serialise to plain JSON strings
+fn as_strings<T: Serialize>(values: &Option<Vec<T>>) -> Vec<String> {
+    values
+        .iter()
+        .flatten()
+        .filter_map(|value| match serde_json::to_value(value) {
+            Ok(Value::String(s)) => Some(s),
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Debug, Default, FromForm)]
+pub struct CompoundSearch {
+    pub name: Option<String>,
+    pub synonym: Option<String>,
+    pub formula: Option<String>,
+    pub min_mass: Option<f64>,
+    pub max_mass: Option<f64>,
+    pub activity: Option<String>,
+}
+
+impl CompoundSearch {
+    fn query(&self, visible: &[&'static str]) -> compounds::BoxedQuery<'static, Pg> {
+        let visible_entries = entries::table
+            .filter(entries::status.eq_any(visible.to_vec()))
+            .select(entries::id);
+        let mut query = all_compounds
+            .filter(compounds::entry_id.eq_any(visible_entries))
+            .into_boxed();
+
+        if let Some(name) = &self.name {
+            query = query.filter(compounds::name.ilike(format!("%{}%", name)));
+        }
+        if let Some(synonym) = &self.synonym {
+            query = query.filter(
+                array_to_string(compounds::synonyms, "|").ilike(format!("%{}%", synonym)),
+            );
+        }
+        if let Some(formula) = &self.formula {
+            query = query.filter(compounds::formula.eq(formula.to_owned()));
+        }
+        if let Some(min_mass) = self.min_mass {
+            query = query.filter(compounds::mol_mass.ge(min_mass));
+        }
+        if let Some(max_mass) = self.max_mass {
+            query = query.filter(compounds::mol_mass.le(max_mass));
+        }
+        if let Some(activity) = &self.activity {
+            query = query.filter(compounds::activities.contains(vec![activity.to_owned()]));
+        }
+
+        query
+    }
+}
+
+impl Compound {
+    pub fn search_count(
+        search: &CompoundSearch,
+        visible: &[&'static str],
+        conn: &PgConnection,
+    ) -> Result<i64, MibigError> {
+        let total = search.query(visible).count().get_result(conn)?;
+        Ok(total)
+    }
+
+    pub fn search(
+        search: &CompoundSearch,
+        visible: &[&'static str],
+        offset: i64,
+        limit: i64,
+        conn: &PgConnection,
+    ) -> Result<Vec<Compound>, MibigError> {
+        let res = search
+            .query(visible)
+            .order((compounds::entry_id.asc(), compounds::compound_id.asc()))
+            .offset(offset)
+            .limit(limit)
+            .load::<Compound>(conn)?;
+        Ok(res)
+    }
+
+    pub fn replace_for_entry(
+        entry_id: &str,
+        new_compounds: &[NewCompound],
+        conn: &PgConnection,
+    ) -> Result<(), MibigError> {
+        diesel::delete(all_compounds.filter(compounds::entry_id.eq(entry_id))).execute(conn)?;
+        diesel::insert_into(compounds::table)
+            .values(new_compounds)
+            .execute(conn)?;
+        Ok(())
+    }
+}
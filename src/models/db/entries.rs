@@ -0,0 +1,387 @@
+use std::fmt;
+use std::str::FromStr;
+
+use diesel;
+use diesel::pg::{Pg, PgConnection};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Text};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::errors::MibigError;
+use crate::models::db::taxa::{Taxon, UNCLASSIFIED};
+use crate::models::query::Query;
+use crate::schema::entries::dsl::entries as all_entries;
+use crate::schema::{entries, taxa};
+
+#[derive(Queryable, Identifiable, Associations, PartialEq, Debug)]
+#[table_name = "entries"]
+pub struct Entry {
+    pub id: String,
+    pub minimal: bool,
+    pub tax_id: Option<i64>,
+    pub organism_name: String,
+    pub biosyn_class: Vec<String>,
+    pub legacy_comment: Option<String>,
+    pub data: Option<Value>,
+    pub status: String,
+    pub needs_review: bool,
+}
+
+#[derive(Insertable)]
+#[table_name = "entries"]
+pub struct NewEntry {
+    pub id: String,
+    pub minimal: bool,
+    pub tax_id: Option<i64>,
+    pub organism_name: String,
+    pub biosyn_class: Vec<String>,
+    pub legacy_comment: Option<String>,
+    pub data: Option<Value>,
+    pub status: String,
+    pub needs_review: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryStatus {
+    Published,
+    Retired,
+    Embargoed,
+    Reserved,
+}
+
+impl EntryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            EntryStatus::Published => "published",
+            EntryStatus::Retired => "retired",
+            EntryStatus::Embargoed => "embargoed",
+            EntryStatus::Reserved => "reserved",
+        }
+    }
+
+    /// Whether an entry with this status may be moved to `next`
+    pub fn can_become(&self, next: EntryStatus) -> bool {
+        use EntryStatus::*;
+        matches!(
+            (*self, next),
+            (Reserved, Embargoed)
+                | (Reserved, Published)
+                | (Reserved, Retired)
+                | (Embargoed, Published)
+                | (Embargoed, Retired)
+                | (Published, Retired)
+                | (Retired, Published)
+        )
+    }
+
+    /// Statuses of the entries a caller is allowed to see, only curators see unreleased entries
+    pub fn visible(curator: bool) -> Vec<&'static str> {
+        let mut statuses = vec![EntryStatus::Published, EntryStatus::Retired];
+        if curator {
+            statuses.push(EntryStatus::Embargoed);
+            statuses.push(EntryStatus::Reserved);
+        }
+        statuses.iter().map(EntryStatus::as_str).collect()
+    }
+}
+
+impl fmt::Display for EntryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for EntryStatus {
+    type Err = MibigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "published" => Ok(EntryStatus::Published),
+            "retired" => Ok(EntryStatus::Retired),
+            "embargoed" => Ok(EntryStatus::Embargoed),
+            "reserved" => Ok(EntryStatus::Reserved),
+            _ => Err(MibigError::InvalidInput(format!(
+                "unknown entry status {}",
+                s
+            ))),
+        }
+    }
+}
+
+sql_function!(fn nextval(sequence: Text) -> BigInt);
+
+type MatchingIds = diesel::dsl::EqAny<entries::id, entries::BoxedQuery<'static, Pg, Text>>;
+
+/// Condition for the entries a structured query matches, usable whatever the entries are joined to
+fn matching_ids(query: &Query, conn: &PgConnection) -> Result<MatchingIds, MibigError> {
+    let matched = all_entries
+        .select(entries::id)
+        .filter(query.compile(conn)?)
+        .into_boxed();
+    Ok(entries::id.eq_any(matched))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, FromFormField)]
+pub enum EntrySort {
+    Accession,
+    Organism,
+    Class,
+}
+
+#[derive(Serialize, Debug)]
+pub struct EntrySummary {
+    pub accession: String,
+    pub minimal: bool,
+    pub biosyn_class: Vec<String>,
+    pub organism_name: String,
+    /// Reserved entries don't have a taxon yet
+    pub taxonomy: Option<Taxon>,
+}
+
+impl From<(Entry, Option<Taxon>)> for EntrySummary {
+    fn from((entry, taxon): (Entry, Option<Taxon>)) -> Self {
+        EntrySummary {
+            accession: entry.id,
+            minimal: entry.minimal,
+            biosyn_class: entry.biosyn_class,
+            organism_name: entry.organism_name,
+            taxonomy: taxon,
+        }
+    }
+}
+
+impl Entry {
+    pub fn count(
+        query: Option<&Query>,
+        visible: &[&str],
+        conn: &PgConnection,
+    ) -> Result<i64, MibigError> {
+        let mut counted = all_entries
+            .filter(entries::status.eq_any(visible))
+            .into_boxed();
+        if let Some(query) = query {
+            counted = counted.filter(matching_ids(query, conn)?);
+        }
+        let total = counted.count().get_result(conn)?;
+        Ok(total)
+    }
+
+    /// Allocate the next free MIBiG accession and create a placeholder entry for it
+    pub fn reserve(conn: &PgConnection) -> Result<String, MibigError> {
+        let number: i64 = diesel::select(nextval("entry_number")).get_result(conn)?;
+        let new_entry = NewEntry {
+            id: format!("BGC{:07}", number),
+            minimal: true,
+            tax_id: None,
+            organism_name: String::new(),
+            biosyn_class: Vec::new(),
+            legacy_comment: None,
+            data: None,
+            status: EntryStatus::Reserved.to_string(),
+            needs_review: false,
+        };
+        let created: Entry = diesel::insert_into(entries::table)
+            .values(&new_entry)
+            .get_result(conn)?;
+        Ok(created.id)
+    }
+
+    pub fn find(accession: &str, conn: &PgConnection) -> Result<Option<Entry>, MibigError> {
+        let res = all_entries.find(accession).first(conn).optional()?;
+        Ok(res)
+    }
+
+    /// Insert a new entry, or update the stored entry with the same accession.
+    ///
+    /// Returns the stored entry and whether it was newly created.
+    pub fn save(new_entry: &NewEntry, conn: &PgConnection) -> Result<(Entry, bool), MibigError> {
+        let existing: Option<Entry> = all_entries.find(&new_entry.id).first(conn).optional()?;
+
+        let existing = match existing {
+            Some(entry) => entry,
+            None => {
+                let created = diesel::insert_into(entries::table)
+                    .values(new_entry)
+                    .get_result(conn)?;
+                return Ok((created, true));
+            }
+        };
+
+        // Data for a reserved accession publishes it, other statuses are managed separately
+        let status = if existing.status == EntryStatus::Reserved.as_str() {
+            new_entry.status.to_owned()
+        } else {
+            existing.status
+        };
+        let updated = diesel::update(all_entries.find(&new_entry.id))
+            .set((
+                entries::minimal.eq(new_entry.minimal),
+                entries::tax_id.eq(new_entry.tax_id),
+                entries::organism_name.eq(&new_entry.organism_name),
+                entries::biosyn_class.eq(&new_entry.biosyn_class),
+                entries::legacy_comment.eq(&new_entry.legacy_comment),
+                entries::data.eq(&new_entry.data),
+                entries::status.eq(status),
+                entries::needs_review.eq(new_entry.needs_review),
+            ))
+            .get_result(conn)?;
+        Ok((updated, false))
+    }
+
+    pub fn list_summaries(
+        sort: EntrySort,
+        descending: bool,
+        query: Option<&Query>,
+        visible: &[&str],
+        offset: i64,
+        limit: i64,
+        conn: &PgConnection,
+    ) -> Result<Vec<EntrySummary>, MibigError> {
+        let mut listed = all_entries
+            .left_join(taxa::table)
+            .filter(entries::status.eq_any(visible))
+            .into_boxed();
+        if let Some(query) = query {
+            listed = listed.filter(matching_ids(query, conn)?);
+        }
+
+        listed = match (sort, descending) {
+            (EntrySort::Accession, false) => listed.order(entries::id.asc()),
+            (EntrySort::Accession, true) => listed.order(entries::id.desc()),
+            (EntrySort::Organism, false) => listed.order(entries::organism_name.asc()),
+            (EntrySort::Organism, true) => listed.order(entries::organism_name.desc()),
+            (EntrySort::Class, false) => listed.order(entries::biosyn_class.asc()),
+            (EntrySort::Class, true) => listed.order(entries::biosyn_class.desc()),
+        };
+
+        // Tie-break on the accession so pages stay stable between requests
+        let res = listed
+            .then_order_by(entries::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .load::<(Entry, Option<Taxon>)>(conn)?;
+
+        Ok(res.into_iter().map(EntrySummary::from).collect())
+    }
+
+    pub fn list_summaries_in(
+        ids: &[String],
+        visible: &[&str],
+        offset: i64,
+        limit: i64,
+        conn: &PgConnection,
+    ) -> Result<Vec<EntrySummary>, MibigError> {
+        let res = all_entries
+            .left_join(taxa::table)
+            .filter(entries::id.eq_any(ids))
+            .filter(entries::status.eq_any(visible))
+            .order(entries::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .load::<(Entry, Option<Taxon>)>(conn)?;
+
+        Ok(res.into_iter().map(EntrySummary::from).collect())
+    }
+
+    pub fn get_data(
+        accession: String,
+        visible: &[&str],
+        conn: &PgConnection,
+    ) -> Result<Value, MibigError> {
+        let data: Option<Value> = all_entries
+            .find(accession)
+            .filter(entries::status.eq_any(visible))
+            .select(entries::data)
+            .first(conn)?;
+        // Entries imported before the full document was stored have nothing to serve
+        data.ok_or(MibigError::DatabaseError(diesel::result::Error::NotFound))
+    }
+
+    /// Fail with `NotFound` unless the entry exists and has one of the given statuses
+    pub fn check_visible(
+        accession: &str,
+        visible: &[&str],
+        conn: &PgConnection,
+    ) -> Result<(), MibigError> {
+        all_entries
+            .find(accession)
+            .filter(entries::status.eq_any(visible))
+            .select(entries::id)
+            .first::<String>(conn)?;
+        Ok(())
+    }
+
+    pub fn list_by_status(
+        status: EntryStatus,
+        query: Option<&Query>,
+        conn: &PgConnection,
+    ) -> Result<Vec<Entry>, MibigError> {
+        let mut listed = all_entries
+            .filter(entries::status.eq(status.as_str()))
+            .into_boxed();
+        if let Some(query) = query {
+            listed = listed.filter(matching_ids(query, conn)?);
+        }
+        let res = listed.order(entries::id.asc()).load::<Entry>(conn)?;
+        Ok(res)
+    }
+
+    /// Stop flagging entries for review once their taxon has a real lineage.
+    ///
+    /// Returns the number of entries that were unflagged.
+    pub fn clear_review_for_classified(conn: &PgConnection) -> Result<usize, MibigError> {
+        let classified = taxa::table
+            .filter(taxa::superkingdom.ne(UNCLASSIFIED))
+            .select(taxa::tax_id.nullable());
+        let res = diesel::update(
+            all_entries
+                .filter(entries::needs_review.eq(true))
+                .filter(entries::tax_id.eq_any(classified)),
+        )
+        .set(entries::needs_review.eq(false))
+        .execute(conn)?;
+        Ok(res)
+    }
+
+    /// Stop flagging the entries of one taxon, after its placeholder lineage was filled in
+    pub fn clear_review_for_taxon(taxon_id: i64, conn: &PgConnection) -> Result<usize, MibigError> {
+        let res = diesel::update(
+            all_entries
+                .filter(entries::needs_review.eq(true))
+                .filter(entries::tax_id.eq(taxon_id)),
+        )
+        .set(entries::needs_review.eq(false))
+        .execute(conn)?;
+        Ok(res)
+    }
+
+    /// Move an entry to a new status, if the lifecycle allows it
+    pub fn set_status(
+        accession: &str,
+        status: EntryStatus,
+        conn: &PgConnection,
+    ) -> Result<Entry, MibigError> {
+        conn.transaction::<_, MibigError, _>(|| {
+            let entry: Entry = all_entries.find(accession).for_update().first(conn)?;
+            let current: EntryStatus = entry.status.parse()?;
+            if !current.can_become(status) {
+                return Err(MibigError::InvalidInput(format!(
+                    "{} can't change from {} to {}",
+                    accession, current, status
+                )));
+            }
+            // A reservation only becomes visible once there is something to show
+            if entry.data.is_none() && status != EntryStatus::Retired {
+                return Err(MibigError::InvalidInput(format!(
+                    "{} has no data yet",
+                    accession
+                )));
+            }
+            let updated = diesel::update(all_entries.find(accession))
+                .set(entries::status.eq(status.as_str()))
+                .get_result(conn)?;
+            Ok(updated)
+        })
+    }
+}
@@ -0,0 +1,123 @@
+use chrono::{DateTime, Utc};
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::errors::MibigError;
+use crate::models::legacy_schema::ChangeLog;
+use crate::schema::entry_revisions;
+use crate::schema::entry_revisions::dsl::entry_revisions as all_entry_revisions;
+
+#[derive(Queryable, Identifiable, Serialize, PartialEq, Debug)]
+#[table_name = "entry_revisions"]
+#[primary_key(revision_id)]
+pub struct EntryRevision {
+    #[serde(skip_serializing)]
+    pub revision_id: i64,
+    pub entry_id: String,
+    pub revision: i32,
+    pub version: String,
+    pub contributors: Vec<String>,
+    pub comments: Vec<String>,
+    pub data: Value,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "entry_revisions"]
+pub struct NewEntryRevision {
+    pub entry_id: String,
+    pub revision: i32,
+    pub version: String,
+    pub contributors: Vec<String>,
+    pub comments: Vec<String>,
+    pub data: Value,
+}
+
+/// An entry revision without its data, for listing the history of an entry
+#[derive(Queryable, Serialize, PartialEq, Debug)]
+pub struct RevisionSummary {
+    pub revision: i32,
+    pub version: String,
+    pub contributors: Vec<String>,
+    pub comments: Vec<String>,
+    pub created: DateTime<Utc>,
+}
+
+impl EntryRevision {
+    pub fn list_for_entry(
+        entry_id: &str,
+        conn: &PgConnection,
+    ) -> Result<Vec<RevisionSummary>, MibigError> {
+        let res = all_entry_revisions
+            .filter(entry_revisions::entry_id.eq(entry_id))
+            .select((
+                entry_revisions::revision,
+                entry_revisions::version,
+                entry_revisions::contributors,
+                entry_revisions::comments,
+                entry_revisions::created,
+            ))
+            .order(entry_revisions::revision.asc())
+            .load::<RevisionSummary>(conn)?;
+        Ok(res)
+    }
+
+    pub fn show(
+        entry_id: &str,
+        revision: i32,
+        conn: &PgConnection,
+    ) -> Result<EntryRevision, MibigError> {
+        let res = all_entry_revisions
+            .filter(entry_revisions::entry_id.eq(entry_id))
+            .filter(entry_revisions::revision.eq(revision))
+            .first(conn)?;
+        Ok(res)
+    }
+
+    pub fn latest(
+        entry_id: &str,
+        conn: &PgConnection,
+    ) -> Result<Option<EntryRevision>, MibigError> {
+        let res = all_entry_revisions
+            .filter(entry_revisions::entry_id.eq(entry_id))
+            .order(entry_revisions::revision.desc())
+            .first(conn)
+            .optional()?;
+        Ok(res)
+    }
+
+    /// Store a new revision of an entry, unless the data is the same as in the latest one.
+    ///
+    /// Returns the number of the added revision.
+    pub fn add(
+        entry_id: &str,
+        changelog: Option<&ChangeLog>,
+        data: &Value,
+        conn: &PgConnection,
+    ) -> Result<Option<i32>, MibigError> {
+        let latest = EntryRevision::latest(entry_id, conn)?;
+        if let Some(latest) = &latest {
+            if &latest.data == data {
+                return Ok(None);
+            }
+        }
+
+        let new_revision = NewEntryRevision {
+            entry_id: entry_id.to_string(),
+            revision: latest.map(|r| r.revision).unwrap_or(0) + 1,
+            version: changelog.map(|c| c.version.to_owned()).unwrap_or_default(),
+            contributors: changelog
+                .map(|c| c.contributors.to_owned())
+                .unwrap_or_default(),
+            comments: changelog.map(|c| c.comments.to_owned()).unwrap_or_default(),
+            data: data.to_owned(),
+        };
+        diesel::insert_into(entry_revisions::table)
+            .values(&new_revision)
+            .execute(conn)?;
+        Ok(Some(new_revision.revision))
+    }
+}
@@ -0,0 +1,244 @@
+use diesel;
+use diesel::dsl::sql;
+use diesel::expression::SqlLiteral;
+use diesel::pg::{Pg, PgConnection};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Float, Text};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::errors::MibigError;
+use crate::models::db::bgc_types::BgcType;
+use crate::models::db::entries::EntrySummary;
+use crate::models::db::stats::{count_types, sorted_counts, TaxonCount, TypeCount};
+use crate::models::db::taxa::{Taxon, TaxonRank};
+use crate::models::legacy_schema;
+use crate::schema::entry_search::dsl::entry_search as all_entry_search;
+use crate::schema::{entries, entry_search, rel_entries_types, taxa};
+use crate::utils::typedefs::sql_types::{Regconfig, Tsquery, Tsvector};
+
+sql_function!(fn to_tsvector(config: Regconfig, document: Text) -> Tsvector);
+sql_function!(fn websearch_to_tsquery(config: Regconfig, query: Text) -> Tsquery);
+sql_function!(fn ts_rank(document: Tsvector, query: Tsquery) -> Float);
+
+diesel_infix_operator!(Matches, " @@ ", backend: Pg);
+
+/// `count(*)` for grouped selects, Diesel won't mix `count_star()` with plain columns
+fn group_count() -> SqlLiteral<BigInt> {
+    sql("count(*)")
+}
+
+/// Scientific names and identifiers aren't English, so don't stem or drop stop words
+fn search_config() -> SqlLiteral<Regconfig> {
+    sql("'simple'")
+}
+
+#[derive(Serialize, Debug)]
+pub struct TaxonFacet {
+    pub rank: TaxonRank,
+    pub counts: Vec<TaxonCount>,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct MinimalFacet {
+    pub minimal: i64,
+    pub complete: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SearchFacets {
+    pub bgc_types: Vec<TypeCount>,
+    pub taxa: TaxonFacet,
+    pub minimal: MinimalFacet,
+}
+
+#[derive(Debug)]
+pub struct SearchResults {
+    pub total: i64,
+    pub entries: Vec<EntrySummary>,
+    pub facets: SearchFacets,
+}
+
+type MatchedIds = entry_search::BoxedQuery<'static, Pg, Text>;
+
+/// Ids of the visible entries matching a query, to use as a subselect
+fn matched_ids(query: &str, visible: &[&str]) -> MatchedIds {
+    let visible: Vec<String> = visible.iter().map(|status| status.to_string()).collect();
+    all_entry_search
+        .filter(Matches::new(
+            entry_search::document,
+            websearch_to_tsquery(search_config(), query.to_string()),
+        ))
+        .filter(
+            entry_search::entry_id.eq_any(
+                entries::table
+                    .filter(entries::status.eq_any(visible))
+                    .select(entries::id),
+            ),
+        )
+        .select(entry_search::entry_id)
+        .into_boxed()
+}
+
+/// The text an entry is found by: compound names and synonyms, organism, gene products and publications
+fn search_text(entry: &legacy_schema::Entry) -> String {
+    let cluster = &entry.cluster;
+    let mut words: Vec<String> = Vec::new();
+
+    words.extend(cluster.compounds.iter().map(|c| c.name.to_owned()));
+    for compound in &cluster.compounds {
+        words.extend(compound.synonyms.iter().flatten().cloned());
+    }
+    words.push(cluster.organism_name.to_owned());
+    for annotation in cluster
+        .genes
+        .iter()
+        .flat_map(|genes| genes.annotations.iter().flatten())
+    {
+        words.extend(annotation.product.to_owned());
+    }
+    for publication in &cluster.publications {
+        if let Ok(Value::String(publication)) = serde_json::to_value(publication) {
+            words.push(publication);
+        }
+    }
+
+    words.join(" ")
+}
+
+pub struct EntrySearch;
+
+impl EntrySearch {
+    /// Rebuild the search document of an entry from its MIBiG data
+    pub fn replace_for_entry(
+        entry_id: &str,
+        entry: &legacy_schema::Entry,
+        conn: &PgConnection,
+    ) -> Result<(), MibigError> {
+        diesel::delete(all_entry_search.filter(entry_search::entry_id.eq(entry_id)))
+            .execute(conn)?;
+        diesel::insert_into(entry_search::table)
+            .values((
+                entry_search::entry_id.eq(entry_id),
+                entry_search::document.eq(to_tsvector(search_config(), search_text(entry))),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Find entries matching a web search style query, best matches first
+    pub fn search(
+        query: &str,
+        rank: TaxonRank,
+        visible: &[&str],
+        offset: i64,
+        limit: i64,
+        conn: &PgConnection,
+    ) -> Result<SearchResults, MibigError> {
+        let total = entries::table
+            .filter(entries::id.eq_any(matched_ids(query, visible)))
+            .count()
+            .get_result(conn)?;
+
+        let rows = all_entry_search
+            .inner_join(entries::table.inner_join(taxa::table))
+            .filter(entry_search::entry_id.eq_any(matched_ids(query, visible)))
+            .select((
+                entries::id,
+                entries::minimal,
+                entries::biosyn_class,
+                entries::organism_name,
+                taxa::all_columns,
+            ))
+            .order((
+                ts_rank(
+                    entry_search::document,
+                    websearch_to_tsquery(search_config(), query),
+                )
+                .desc(),
+                entries::id.asc(),
+            ))
+            .offset(offset)
+            .limit(limit)
+            .load::<(String, bool, Vec<String>, String, Taxon)>(conn)?;
+
+        let entries = rows
+            .into_iter()
+            .map(
+                |(accession, minimal, biosyn_class, organism_name, taxonomy)| EntrySummary {
+                    accession,
+                    minimal,
+                    biosyn_class,
+                    organism_name,
+                    taxonomy: Some(taxonomy),
+                },
+            )
+            .collect();
+
+        Ok(SearchResults {
+            total,
+            entries,
+            facets: facets(query, rank, visible, conn)?,
+        })
+    }
+}
+
+/// Break down all matches, not just the current page, by type, taxon and completeness
+fn facets(
+    query: &str,
+    rank: TaxonRank,
+    visible: &[&str],
+    conn: &PgConnection,
+) -> Result<SearchFacets, MibigError> {
+    let assignments = rel_entries_types::table
+        .filter(rel_entries_types::entry_id.eq_any(matched_ids(query, visible)))
+        .select((rel_entries_types::entry_id, rel_entries_types::bgc_type_id))
+        .load::<(String, i32)>(conn)?;
+    let types = BgcType::all(conn)?;
+    let mut bgc_types = count_types(&types, assignments);
+    bgc_types.retain(|count| count.count > 0);
+
+    macro_rules! count_by {
+        ($column: expr) => {
+            taxa::table
+                .inner_join(entries::table)
+                .filter(entries::id.eq_any(matched_ids(query, visible)))
+                .group_by($column)
+                .select(($column, group_count()))
+                .load::<(String, i64)>(conn)?
+        };
+    }
+    let taxon_counts = match rank {
+        TaxonRank::Superkingdom => count_by!(taxa::superkingdom),
+        TaxonRank::Kingdom => count_by!(taxa::kingdom),
+        TaxonRank::Phylum => count_by!(taxa::phylum),
+        TaxonRank::Class => count_by!(taxa::class),
+        TaxonRank::Order => count_by!(taxa::taxonomic_order),
+        TaxonRank::Family => count_by!(taxa::family),
+        TaxonRank::Genus => count_by!(taxa::genus),
+        TaxonRank::Species => count_by!(taxa::species),
+    };
+
+    let mut minimal = MinimalFacet::default();
+    let completeness = entries::table
+        .filter(entries::id.eq_any(matched_ids(query, visible)))
+        .group_by(entries::minimal)
+        .select((entries::minimal, group_count()))
+        .load::<(bool, i64)>(conn)?;
+    for (is_minimal, count) in completeness {
+        if is_minimal {
+            minimal.minimal = count;
+        } else {
+            minimal.complete = count;
+        }
+    }
+
+    Ok(SearchFacets {
+        bgc_types,
+        taxa: TaxonFacet {
+            rank,
+            counts: sorted_counts(taxon_counts.into_iter().collect()),
+        },
+        minimal,
+    })
+}
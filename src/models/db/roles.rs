@@ -0,0 +1,140 @@
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use rocket::http::Status;
+use rocket::outcome::try_outcome;
+use rocket::request::{FromRequest, Outcome, Request};
+use serde::Serialize;
+
+use crate::errors::MibigError;
+use crate::models::db::submitters::Submitter;
+use crate::models::db::tokens::Token;
+use crate::schema::rel_submitters_roles::dsl::rel_submitters_roles as all_rel_submitters_roles;
+use crate::schema::roles::dsl::roles as all_roles;
+use crate::schema::{rel_submitters_roles, roles};
+use crate::DBPool;
+
+pub const ADMIN: &str = "admin";
+pub const CURATOR: &str = "curator";
+pub const SUBMITTER: &str = "submitter";
+
+#[derive(Queryable, Identifiable, Serialize, PartialEq, Debug)]
+#[table_name = "roles"]
+#[primary_key(role_id)]
+pub struct Role {
+    pub role_id: i32,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "rel_submitters_roles"]
+pub struct NewSubmitterRole {
+    pub user_id: String,
+    pub role_id: i32,
+}
+
+impl Role {
+    pub fn all(conn: &PgConnection) -> Result<Vec<Role>, MibigError> {
+        let res = all_roles.order(roles::role_id).load::<Role>(conn)?;
+        Ok(res)
+    }
+
+    pub fn names_for_user(user_id: &str, conn: &PgConnection) -> Result<Vec<String>, MibigError> {
+        let res = all_rel_submitters_roles
+            .inner_join(roles::table)
+            .filter(rel_submitters_roles::user_id.eq(user_id))
+            .select(roles::name)
+            .order(roles::role_id)
+            .load::<Option<String>>(conn)?;
+        Ok(res.into_iter().flatten().collect())
+    }
+
+    /// Replace all roles of a user with the given role names
+    pub fn set_for_user(
+        user_id: &str,
+        names: &[String],
+        conn: &PgConnection,
+    ) -> Result<(), MibigError> {
+        let known = Role::all(conn)?;
+        let mut new_roles: Vec<NewSubmitterRole> = Vec::new();
+        for name in names {
+            match known.iter().find(|r| r.name.as_ref() == Some(name)) {
+                Some(role) => new_roles.push(NewSubmitterRole {
+                    user_id: user_id.to_string(),
+                    role_id: role.role_id,
+                }),
+                None => return Err(MibigError::InvalidRole(name.to_owned())),
+            }
+        }
+
+        conn.transaction::<_, MibigError, _>(|| {
+            diesel::delete(
+                all_rel_submitters_roles.filter(rel_submitters_roles::user_id.eq(user_id)),
+            )
+            .execute(conn)?;
+            diesel::insert_into(rel_submitters_roles::table)
+                .values(&new_roles)
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+/// Resolve the user a request's token belongs to, if that user is active and has the role
+async fn user_with_role(req: &Request<'_>, role: &'static str) -> Outcome<Submitter, ()> {
+    let token = try_outcome!(req.guard::<Token>().await);
+    let pool = try_outcome!(req.guard::<DBPool>().await);
+
+    let res = pool
+        .run(move |c| {
+            let user = Submitter::show(&token.user_id, c)?;
+            let roles = Role::names_for_user(&user.user_id, c)?;
+            Ok::<_, MibigError>((user, roles))
+        })
+        .await;
+
+    match res {
+        Ok((user, roles)) => {
+            if user.active && roles.iter().any(|r| r == role) {
+                Outcome::Success(user)
+            } else {
+                Outcome::Failure((Status::Forbidden, ()))
+            }
+        }
+        Err(MibigError::DatabaseError(diesel::result::Error::NotFound)) => {
+            Outcome::Failure((Status::Unauthorized, ()))
+        }
+        Err(_) => Outcome::Failure((Status::InternalServerError, ())),
+    }
+}
+
+macro_rules! implement_role_guard {
+    ($guard: ident, $role: expr) => {
+        pub struct $guard(pub Submitter);
+
+        implement_role_guard!(@from_request $guard, $role, $guard);
+    };
+    // Guards for routes that only care about the role, not about who the user is
+    ($guard: ident, $role: expr, unit) => {
+        pub struct $guard;
+
+        implement_role_guard!(@from_request $guard, $role, |_| $guard);
+    };
+    (@from_request $guard: ident, $role: expr, $wrap: expr) => {
+        #[rocket::async_trait]
+        impl<'r> FromRequest<'r> for $guard {
+            type Error = ();
+
+            async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ()> {
+                user_with_role(req, $role).await.map($wrap)
+            }
+        }
+    };
+}
+
+implement_role_guard!(AdminUser, ADMIN, unit);
+implement_role_guard!(CuratorUser, CURATOR);
+implement_role_guard!(SubmitterUser, SUBMITTER);
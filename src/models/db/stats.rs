@@ -0,0 +1,139 @@
+use std::collections::{HashMap, HashSet};
+
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::errors::MibigError;
+use crate::models::db::bgc_types::BgcType;
+use crate::models::db::entries::Entry;
+use crate::models::db::submission_requests::SubmissionRequest;
+use crate::schema::{entries, rel_entries_types, taxa};
+
+#[derive(Serialize, Debug)]
+pub struct TypeCount {
+    pub term: String,
+    pub name: String,
+    pub parent: Option<String>,
+    pub count: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TaxonCount {
+    pub name: String,
+    pub count: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RepositoryStats {
+    pub total_entries: i64,
+    pub complete_entries: i64,
+    pub minimal_entries: i64,
+    pub pending_requests: i64,
+    pub bgc_types: Vec<TypeCount>,
+    pub phyla: Vec<TaxonCount>,
+    pub genera: Vec<TaxonCount>,
+}
+
+impl RepositoryStats {
+    pub fn collect(visible: &[&str], conn: &PgConnection) -> Result<RepositoryStats, MibigError> {
+        let total_entries = Entry::count(None, visible, conn)?;
+        let (phyla, genera) = taxon_counts(visible, conn)?;
+        let minimal_entries = count_filled(true, visible, conn)?;
+        let complete_entries = count_filled(false, visible, conn)?;
+
+        Ok(RepositoryStats {
+            total_entries,
+            complete_entries,
+            minimal_entries,
+            pending_requests: SubmissionRequest::count(conn)?,
+            bgc_types: type_counts(visible, conn)?,
+            phyla,
+            genera,
+        })
+    }
+}
+
+/// Count the visible entries with or without the minimal flag, leaving out reserved
+/// accessions that don't have any data yet
+fn count_filled(minimal: bool, visible: &[&str], conn: &PgConnection) -> Result<i64, MibigError> {
+    let res = entries::table
+        .filter(entries::status.eq_any(visible))
+        .filter(entries::data.is_not_null())
+        .filter(entries::minimal.eq(minimal))
+        .count()
+        .get_result(conn)?;
+    Ok(res)
+}
+
+/// Count entries per BGC type, where an entry of a subtype also counts towards its parents
+fn type_counts(visible: &[&str], conn: &PgConnection) -> Result<Vec<TypeCount>, MibigError> {
+    let types = BgcType::all(conn)?;
+    let assignments = rel_entries_types::table
+        .inner_join(entries::table)
+        .filter(entries::status.eq_any(visible))
+        .select((rel_entries_types::entry_id, rel_entries_types::bgc_type_id))
+        .load::<(String, i32)>(conn)?;
+
+    Ok(count_types(&types, assignments))
+}
+
+/// Count the entries in `(entry_id, bgc_type_id)` assignments for every type
+pub fn count_types(types: &[BgcType], assignments: Vec<(String, i32)>) -> Vec<TypeCount> {
+    let mut entries_by_type: HashMap<i32, HashSet<String>> = HashMap::new();
+    for (entry_id, bgc_type_id) in assignments {
+        entries_by_type
+            .entry(bgc_type_id)
+            .or_default()
+            .insert(entry_id);
+    }
+
+    types
+        .iter()
+        .map(|bgc_type| {
+            let mut matching: HashSet<&String> = HashSet::new();
+            for id in bgc_type.descendant_ids(types) {
+                matching.extend(entries_by_type.get(&id).into_iter().flatten());
+            }
+            TypeCount {
+                term: bgc_type.term.to_owned(),
+                name: bgc_type.name.to_owned(),
+                parent: types
+                    .iter()
+                    .find(|t| Some(t.bgc_type_id) == bgc_type.parent_id)
+                    .map(|t| t.term.to_owned()),
+                count: matching.len() as i64,
+            }
+        })
+        .collect()
+}
+
+/// Count entries per phylum and per genus, most common first
+fn taxon_counts(
+    visible: &[&str],
+    conn: &PgConnection,
+) -> Result<(Vec<TaxonCount>, Vec<TaxonCount>), MibigError> {
+    let lineages = entries::table
+        .inner_join(taxa::table)
+        .filter(entries::status.eq_any(visible))
+        .select((taxa::phylum, taxa::genus))
+        .load::<(String, String)>(conn)?;
+
+    let mut phyla: HashMap<String, i64> = HashMap::new();
+    let mut genera: HashMap<String, i64> = HashMap::new();
+    for (phylum, genus) in lineages {
+        *phyla.entry(phylum).or_default() += 1;
+        *genera.entry(genus).or_default() += 1;
+    }
+
+    Ok((sorted_counts(phyla), sorted_counts(genera)))
+}
+
+pub fn sorted_counts(counts: HashMap<String, i64>) -> Vec<TaxonCount> {
+    let mut res: Vec<TaxonCount> = counts
+        .into_iter()
+        .map(|(name, count)| TaxonCount { name, count })
+        .collect();
+    res.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    res
+}
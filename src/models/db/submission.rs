@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::MibigError;
+use crate::models::db::entries::{Entry, EntryStatus};
+use crate::models::db::submission_requests::SubmissionRequest;
+use crate::models::legacy_schema;
+use crate::schema::entries::dsl::entries as all_entries;
+use crate::schema::submissions;
+use crate::schema::submissions::dsl::submissions as all_submissions;
+
+#[derive(Queryable, Identifiable, Serialize, PartialEq, Debug)]
+#[table_name = "submissions"]
+#[primary_key(submission_id)]
+pub struct Submission {
+    pub submission_id: i64,
+    pub entry_id: String,
+    pub user_id: String,
+    pub data: Value,
+    pub created: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "submissions"]
+pub struct NewSubmission {
+    pub entry_id: String,
+    pub user_id: String,
+    pub data: Value,
+}
+
+/// Check a draft parses as a MIBiG entry and return the accession it is for
+fn draft_accession(data: &Value) -> Result<String, MibigError> {
+    let entry = legacy_schema::Entry::deserialize(data)
+        .map_err(|e| MibigError::InvalidInput(format!("invalid MIBiG entry: {}", e)))?;
+    Ok(entry.cluster.mibig_accession)
+}
+
+/// Make sure the user may work on a draft for the given entry.
+///
+/// Retired entries can't be changed, and reserved entries belong to whoever requested them.
+fn check_editable(accession: &str, user_id: &str, conn: &PgConnection) -> Result<(), MibigError> {
+    let entry: Entry = all_entries.find(accession).first(conn)?;
+    match entry.status.parse()? {
+        EntryStatus::Retired => Err(MibigError::InvalidInput(format!(
+            "{} is retired",
+            accession
+        ))),
+        EntryStatus::Reserved => match SubmissionRequest::requester_for(accession, conn)? {
+            Some(requester) if requester == user_id => Ok(()),
+            _ => Err(MibigError::InvalidInput(format!(
+                "{} is reserved for a different user",
+                accession
+            ))),
+        },
+        _ => Ok(()),
+    }
+}
+
+impl Submission {
+    pub fn create(
+        user_id: &str,
+        data: Value,
+        conn: &PgConnection,
+    ) -> Result<Submission, MibigError> {
+        let accession = draft_accession(&data)?;
+        check_editable(&accession, user_id, conn)?;
+
+        let new_submission = NewSubmission {
+            entry_id: accession,
+            user_id: user_id.to_string(),
+            data,
+        };
+        let inserted = diesel::insert_into(submissions::table)
+            .values(&new_submission)
+            .get_result::<Submission>(conn)?;
+        Ok(inserted)
+    }
+
+    pub fn list(conn: &PgConnection) -> Result<Vec<Submission>, MibigError> {
+        let res = all_submissions
+            .order(submissions::submission_id)
+            .load::<Submission>(conn)?;
+        Ok(res)
+    }
+
+    pub fn list_for_user(
+        user_id: &str,
+        conn: &PgConnection,
+    ) -> Result<Vec<Submission>, MibigError> {
+        let res = all_submissions
+            .filter(submissions::user_id.eq(user_id))
+            .order(submissions::submission_id)
+            .load::<Submission>(conn)?;
+        Ok(res)
+    }
+
+    pub fn show(id: i64, conn: &PgConnection) -> Result<Submission, MibigError> {
+        let submission = all_submissions.find(id).first(conn)?;
+        Ok(submission)
+    }
+
+    /// Replace the data of a draft with a new upload for the same entry
+    pub fn update_data(
+        id: i64,
+        data: Value,
+        conn: &PgConnection,
+    ) -> Result<Submission, MibigError> {
+        let submission = Submission::show(id, conn)?;
+        let accession = draft_accession(&data)?;
+        if accession != submission.entry_id {
+            return Err(MibigError::InvalidInput(format!(
+                "submission {} is for {}, not {}",
+                id, submission.entry_id, accession
+            )));
+        }
+        check_editable(&accession, &submission.user_id, conn)?;
+
+        let updated = diesel::update(all_submissions.find(id))
+            .set((
+                submissions::data.eq(data),
+                submissions::updated.eq(Utc::now()),
+            ))
+            .get_result::<Submission>(conn)?;
+        Ok(updated)
+    }
+
+    pub fn delete(id: i64, conn: &PgConnection) -> Result<(), MibigError> {
+        Submission::show(id, conn)?;
+        diesel::delete(all_submissions.find(id)).execute(conn)?;
+        Ok(())
+    }
+}
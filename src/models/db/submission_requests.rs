@@ -1,12 +1,15 @@
+use chrono::{DateTime, Utc};
 use diesel;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
+use serde::Serialize;
 
 use crate::errors::MibigError;
+use crate::models::db::entries::Entry;
 use crate::schema::submission_requests;
 use crate::schema::submission_requests::dsl::submission_requests as all_submission_requests;
 
-#[derive(Queryable, Identifiable, Associations, PartialEq, Debug)]
+#[derive(Queryable, Identifiable, Associations, Serialize, PartialEq, Debug)]
 #[table_name = "submission_requests"]
 pub struct SubmissionRequest {
     pub id: i64,
@@ -15,8 +18,17 @@ pub struct SubmissionRequest {
     pub accession: String,
     pub start_nt: Option<i32>,
     pub end_nt: Option<i32>,
+    pub status: String,
+    pub entry_id: Option<String>,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolution_reason: Option<String>,
 }
 
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_APPROVED: &str = "approved";
+pub const STATUS_REJECTED: &str = "rejected";
+
 #[derive(Insertable, Debug)]
 #[table_name = "submission_requests"]
 pub struct NewSubmissionRequest {
@@ -56,6 +68,47 @@ impl SubmissionRequest {
         Ok(res)
     }
 
+    pub fn list_for_user(
+        user_id: &str,
+        conn: &PgConnection,
+    ) -> Result<Vec<SubmissionRequest>, MibigError> {
+        let res = all_submission_requests
+            .filter(submission_requests::user_id.eq(user_id))
+            .order(submission_requests::id)
+            .load::<SubmissionRequest>(conn)?;
+        Ok(res)
+    }
+
+    pub fn list_pending(conn: &PgConnection) -> Result<Vec<SubmissionRequest>, MibigError> {
+        let res = all_submission_requests
+            .filter(submission_requests::status.eq(STATUS_PENDING))
+            .order(submission_requests::id)
+            .load::<SubmissionRequest>(conn)?;
+        Ok(res)
+    }
+
+    /// User who requested the reservation of an accession, if any
+    pub fn requester_for(
+        entry_id: &str,
+        conn: &PgConnection,
+    ) -> Result<Option<String>, MibigError> {
+        let res = all_submission_requests
+            .filter(submission_requests::entry_id.eq(entry_id))
+            .select(submission_requests::user_id)
+            .first(conn)
+            .optional()?;
+        Ok(res)
+    }
+
+    /// Number of requests still waiting for a curator
+    pub fn count(conn: &PgConnection) -> Result<i64, MibigError> {
+        let total = all_submission_requests
+            .filter(submission_requests::status.eq(STATUS_PENDING))
+            .count()
+            .get_result(conn)?;
+        Ok(total)
+    }
+
     pub fn show(id: i64, conn: &PgConnection) -> Result<SubmissionRequest, MibigError> {
         let req = all_submission_requests.find(id).first(conn)?;
         Ok(req)
@@ -66,4 +119,57 @@ impl SubmissionRequest {
         diesel::delete(all_submission_requests.find(id)).execute(conn)?;
         Ok(())
     }
+
+    /// Lock a request for resolving, failing if it was already resolved
+    fn lock_pending(id: i64, conn: &PgConnection) -> Result<SubmissionRequest, MibigError> {
+        let req: SubmissionRequest = all_submission_requests.find(id).for_update().first(conn)?;
+        if req.status != STATUS_PENDING {
+            return Err(MibigError::InvalidInput(format!(
+                "request {} is already {}",
+                id, req.status
+            )));
+        }
+        Ok(req)
+    }
+
+    /// Approve a pending request, reserving a new accession for it.
+    ///
+    /// Returns the reserved accession.
+    pub fn approve(id: i64, curator_id: &str, conn: &PgConnection) -> Result<String, MibigError> {
+        conn.transaction::<_, MibigError, _>(|| {
+            SubmissionRequest::lock_pending(id, conn)?;
+
+            let accession = Entry::reserve(conn)?;
+            diesel::update(all_submission_requests.find(id))
+                .set((
+                    submission_requests::status.eq(STATUS_APPROVED),
+                    submission_requests::entry_id.eq(&accession),
+                    submission_requests::resolved_by.eq(curator_id),
+                    submission_requests::resolved_at.eq(Utc::now()),
+                ))
+                .execute(conn)?;
+            Ok(accession)
+        })
+    }
+
+    pub fn reject(
+        id: i64,
+        curator_id: &str,
+        reason: &str,
+        conn: &PgConnection,
+    ) -> Result<SubmissionRequest, MibigError> {
+        conn.transaction::<_, MibigError, _>(|| {
+            SubmissionRequest::lock_pending(id, conn)?;
+
+            let req = diesel::update(all_submission_requests.find(id))
+                .set((
+                    submission_requests::status.eq(STATUS_REJECTED),
+                    submission_requests::resolved_by.eq(curator_id),
+                    submission_requests::resolved_at.eq(Utc::now()),
+                    submission_requests::resolution_reason.eq(reason),
+                ))
+                .get_result(conn)?;
+            Ok(req)
+        })
+    }
 }
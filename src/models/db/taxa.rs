@@ -1,9 +1,19 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::errors::MibigError;
 use crate::schema::*;
 
-#[derive(Identifiable, Queryable, PartialEq, Debug)]
+#[derive(Identifiable, Queryable, AsChangeset, Serialize, PartialEq, Debug)]
 #[table_name = "taxa"]
 #[primary_key(tax_id)]
 pub struct Taxon {
+    #[serde(skip_serializing)]
     pub tax_id: i64,
     pub ncbi_taxid: i64,
     pub superkingdom: String,
@@ -17,7 +27,7 @@ pub struct Taxon {
     pub name: String,
 }
 
-#[derive(Insertable, PartialEq, Debug)]
+#[derive(Insertable, AsChangeset, PartialEq, Debug)]
 #[table_name = "taxa"]
 pub struct NewTaxon {
     pub ncbi_taxid: i64,
@@ -31,3 +41,228 @@ pub struct NewTaxon {
     pub species: String,
     pub name: String,
 }
+
+/// Placeholder name at every rank of a lineage the taxon cache couldn't resolve
+pub const UNCLASSIFIED: &str = "Unclassified";
+
+impl NewTaxon {
+    /// A taxon for an organism with an unknown taxid, to be fixed up by a curator
+    pub fn unclassified(ncbi_taxid: i64, name: &str) -> Self {
+        NewTaxon {
+            ncbi_taxid,
+            superkingdom: UNCLASSIFIED.to_string(),
+            kingdom: UNCLASSIFIED.to_string(),
+            phylum: UNCLASSIFIED.to_string(),
+            class: UNCLASSIFIED.to_string(),
+            taxonomic_order: UNCLASSIFIED.to_string(),
+            family: UNCLASSIFIED.to_string(),
+            genus: UNCLASSIFIED.to_string(),
+            species: UNCLASSIFIED.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, FromFormField)]
+#[serde(rename_all = "lowercase")]
+pub enum TaxonRank {
+    Superkingdom,
+    Kingdom,
+    Phylum,
+    Class,
+    Order,
+    Family,
+    Genus,
+    Species,
+}
+
+impl TaxonRank {
+    /// All ranks, from the root of the lineage down
+    pub const ALL: [TaxonRank; 8] = [
+        TaxonRank::Superkingdom,
+        TaxonRank::Kingdom,
+        TaxonRank::Phylum,
+        TaxonRank::Class,
+        TaxonRank::Order,
+        TaxonRank::Family,
+        TaxonRank::Genus,
+        TaxonRank::Species,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            TaxonRank::Superkingdom => "superkingdom",
+            TaxonRank::Kingdom => "kingdom",
+            TaxonRank::Phylum => "phylum",
+            TaxonRank::Class => "class",
+            TaxonRank::Order => "order",
+            TaxonRank::Family => "family",
+            TaxonRank::Genus => "genus",
+            TaxonRank::Species => "species",
+        }
+    }
+
+    /// The ranks from superkingdom down to and including this one
+    pub fn lineage(&self) -> &'static [TaxonRank] {
+        &TaxonRank::ALL[..=*self as usize]
+    }
+
+    /// The ranks below this one, down to and including `depth`
+    fn below(&self, depth: TaxonRank) -> &'static [TaxonRank] {
+        depth.lineage().get(*self as usize + 1..).unwrap_or(&[])
+    }
+}
+
+impl fmt::Display for TaxonRank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for TaxonRank {
+    type Err = MibigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TaxonRank::ALL
+            .iter()
+            .find(|rank| rank.as_str().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| MibigError::InvalidInput(format!("unknown taxonomic rank {}", s)))
+    }
+}
+
+/// A taxon in the lineages of the repository entries, with the number of entries below it
+#[derive(Serialize, Debug)]
+pub struct TaxonNode {
+    pub rank: TaxonRank,
+    pub name: String,
+    pub count: i64,
+    pub children: Vec<TaxonNode>,
+}
+
+/// Group lineages by their name at the first rank, then recurse into the following ranks
+fn nodes_for(lineages: &[&Taxon], ranks: &[TaxonRank]) -> Vec<TaxonNode> {
+    let (rank, rest) = match ranks.split_first() {
+        Some(split) => split,
+        None => return Vec::new(),
+    };
+
+    let mut groups: HashMap<&str, Vec<&Taxon>> = HashMap::new();
+    for lineage in lineages {
+        groups
+            .entry(lineage.at_rank(*rank))
+            .or_default()
+            .push(lineage);
+    }
+
+    let mut nodes: Vec<TaxonNode> = groups
+        .into_iter()
+        .map(|(name, members)| TaxonNode {
+            rank: *rank,
+            name: name.to_string(),
+            count: members.len() as i64,
+            children: nodes_for(&members, rest),
+        })
+        .collect();
+    nodes.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    nodes
+}
+
+impl Taxon {
+    pub fn all(conn: &PgConnection) -> Result<Vec<Taxon>, MibigError> {
+        let res = taxa::table.order(taxa::tax_id).load::<Taxon>(conn)?;
+        Ok(res)
+    }
+
+    pub fn save(&self, conn: &PgConnection) -> Result<(), MibigError> {
+        diesel::update(taxa::table.find(self.tax_id))
+            .set(self)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Whether this is a placeholder lineage from `NewTaxon::unclassified`
+    pub fn is_unclassified(&self) -> bool {
+        self.superkingdom == UNCLASSIFIED
+    }
+
+    /// The lineage of every visible entry, so taxa with several entries appear several times
+    fn entry_lineages(visible: &[&str], conn: &PgConnection) -> Result<Vec<Taxon>, MibigError> {
+        let res = entries::table
+            .inner_join(taxa::table)
+            .filter(entries::status.eq_any(visible))
+            .select(taxa::all_columns)
+            .load::<Taxon>(conn)?;
+        Ok(res)
+    }
+
+    /// The taxonomy of the visible entries from superkingdom down to `depth`
+    pub fn tree(
+        depth: TaxonRank,
+        visible: &[&str],
+        conn: &PgConnection,
+    ) -> Result<Vec<TaxonNode>, MibigError> {
+        let lineages = Taxon::entry_lineages(visible, conn)?;
+        let lineages: Vec<&Taxon> = lineages.iter().collect();
+        Ok(nodes_for(&lineages, depth.lineage()))
+    }
+
+    /// The part of the taxonomy below one taxon, down to `depth`
+    pub fn subtree(
+        rank: TaxonRank,
+        name: &str,
+        depth: TaxonRank,
+        visible: &[&str],
+        conn: &PgConnection,
+    ) -> Result<TaxonNode, MibigError> {
+        let lineages = Taxon::entry_lineages(visible, conn)?;
+        let lineages: Vec<&Taxon> = lineages
+            .iter()
+            .filter(|lineage| lineage.at_rank(rank).eq_ignore_ascii_case(name))
+            .collect();
+        let first = lineages
+            .first()
+            .ok_or(MibigError::DatabaseError(diesel::result::Error::NotFound))?;
+
+        Ok(TaxonNode {
+            rank,
+            name: first.at_rank(rank).to_string(),
+            count: lineages.len() as i64,
+            children: nodes_for(&lineages, rank.below(depth)),
+        })
+    }
+
+    /// Accessions of the visible entries from an NCBI taxid, failing for unknown taxids
+    pub fn entry_ids(
+        ncbi_taxid: i64,
+        visible: &[&str],
+        conn: &PgConnection,
+    ) -> Result<Vec<String>, MibigError> {
+        taxa::table
+            .filter(taxa::ncbi_taxid.eq(ncbi_taxid))
+            .select(taxa::tax_id)
+            .first::<i64>(conn)?;
+        let res = entries::table
+            .inner_join(taxa::table)
+            .filter(taxa::ncbi_taxid.eq(ncbi_taxid))
+            .filter(entries::status.eq_any(visible))
+            .select(entries::id)
+            .order(entries::id.asc())
+            .load::<String>(conn)?;
+        Ok(res)
+    }
+
+    /// Name of the lineage member at the given rank
+    pub fn at_rank(&self, rank: TaxonRank) -> &str {
+        match rank {
+            TaxonRank::Superkingdom => &self.superkingdom,
+            TaxonRank::Kingdom => &self.kingdom,
+            TaxonRank::Phylum => &self.phylum,
+            TaxonRank::Class => &self.class,
+            TaxonRank::Order => &self.taxonomic_order,
+            TaxonRank::Family => &self.family,
+            TaxonRank::Genus => &self.genus,
+            TaxonRank::Species => &self.species,
+        }
+    }
+}
@@ -1,13 +1,13 @@
 use chrono::{DateTime, Duration, Utc};
+use std::fmt;
 
 use diesel;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 
 use rocket::http::Status;
-use rocket::http::{Cookie, CookieJar};
 use rocket::outcome::try_outcome;
-use rocket::request::{self, FromRequest, Outcome, Request};
+use rocket::request::{FromRequest, Outcome, Request};
 
 use crate::errors::MibigError;
 use crate::schema::tokens;
@@ -15,6 +15,23 @@ use crate::schema::tokens::dsl::tokens as all_tokens;
 use crate::utils::generate_token_id;
 use crate::DBPool;
 
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TokenScope {
+    Activation,
+    Authentication,
+    PasswordReset,
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TokenScope::Activation => write!(f, "Activation"),
+            TokenScope::Authentication => write!(f, "Authentication"),
+            TokenScope::PasswordReset => write!(f, "PasswordReset"),
+        }
+    }
+}
+
 #[derive(Insertable, Identifiable, Queryable, PartialEq, Debug)]
 #[table_name = "tokens"]
 #[primary_key(hash)]
@@ -26,13 +43,13 @@ pub struct Token {
 }
 
 impl Token {
-    pub fn new(user_id: String, ttl: Duration, scope: String) -> Result<Token, MibigError> {
+    pub fn new(user_id: String, ttl: Duration, scope: TokenScope) -> Result<Token, MibigError> {
         let hash = generate_token_id()?;
         let token = Token {
             hash: hash.to_owned(),
             user_id: user_id.to_owned(),
             expiry: Utc::now() + ttl,
-            scope: scope.to_owned(),
+            scope: scope.to_string(),
         };
         Ok(token)
     }
@@ -42,6 +59,20 @@ impl Token {
         Ok(token)
     }
 
+    /// Look up a token that has not expired yet and was issued for the given scope
+    pub fn show_valid(
+        hash: String,
+        scope: TokenScope,
+        conn: &PgConnection,
+    ) -> Result<Token, MibigError> {
+        let token = all_tokens
+            .find(hash)
+            .filter(tokens::scope.eq(scope.to_string()))
+            .filter(tokens::expiry.gt(Utc::now()))
+            .first(conn)?;
+        Ok(token)
+    }
+
     pub fn all(conn: &PgConnection) -> Result<Vec<Token>, MibigError> {
         let res = all_tokens
             .order(tokens::user_id.desc())
@@ -64,14 +95,25 @@ impl Token {
         Ok(())
     }
 
+    pub fn delete(hash: String, conn: &PgConnection) -> Result<(), MibigError> {
+        diesel::delete(all_tokens.find(hash)).execute(conn)?;
+        Ok(())
+    }
+
+    pub fn delete_expired(conn: &PgConnection) -> Result<usize, MibigError> {
+        let deleted =
+            diesel::delete(all_tokens.filter(tokens::expiry.le(Utc::now()))).execute(conn)?;
+        Ok(deleted)
+    }
+
     pub fn delete_all_for_user(
-        scope: String,
+        scope: TokenScope,
         user_id: String,
         conn: &PgConnection,
     ) -> Result<(), MibigError> {
         diesel::delete(
             all_tokens
-                .filter(tokens::scope.eq(&scope))
+                .filter(tokens::scope.eq(scope.to_string()))
                 .filter(tokens::user_id.eq(&user_id)),
         )
         .execute(conn)?;
@@ -99,10 +141,19 @@ impl<'r> FromRequest<'r> for Token {
                 }
             }
         }
-        if let Ok(token) = pool.run(|c| Token::show(token_id, c)).await {
-            Outcome::Success(token)
-        } else {
-            Outcome::Failure((Status::BadRequest, ()))
+        if token_id.is_empty() {
+            return Outcome::Failure((Status::Unauthorized, ()));
+        }
+        match pool
+            .run(|c| Token::show_valid(token_id, TokenScope::Authentication, c))
+            .await
+        {
+            Ok(token) => Outcome::Success(token),
+            // Unknown, expired and tokens for other scopes all end up here
+            Err(MibigError::DatabaseError(diesel::result::Error::NotFound)) => {
+                Outcome::Failure((Status::Unauthorized, ()))
+            }
+            Err(_) => Outcome::Failure((Status::InternalServerError, ())),
         }
     }
 }
@@ -12,6 +12,7 @@ pub mod publication;
 pub mod ripp;
 pub mod saccharide;
 pub mod terpene;
+pub mod validation;
 
 pub use alkaloid::Alkaloid;
 pub use compound::Compound;
@@ -72,11 +73,72 @@ pub enum BiosyntheticClass {
     Other,
 }
 
+impl BiosyntheticClass {
+    /// The `bgc_types` term of the top-level class
+    pub fn term(&self) -> &'static str {
+        match self {
+            BiosyntheticClass::Alkaloid => "alkaloid",
+            BiosyntheticClass::NRP => "nrps",
+            BiosyntheticClass::Polyketide => "pks",
+            BiosyntheticClass::RiPP => "ripp",
+            BiosyntheticClass::Saccharide => "saccharide",
+            BiosyntheticClass::Terpene => "terpene",
+            BiosyntheticClass::Other => "other",
+        }
+    }
+}
+
+impl Cluster {
+    /// All subclass annotations given in the class-specific blocks for a class
+    pub fn subclasses(&self, class: BiosyntheticClass) -> Vec<String> {
+        let mut subclasses: Vec<String> = Vec::new();
+        match class {
+            BiosyntheticClass::Alkaloid => {
+                if let Some(alkaloid) = &self.alkaloid {
+                    subclasses.extend(alkaloid.subclass.to_owned());
+                }
+            }
+            BiosyntheticClass::NRP => {
+                if let Some(nrp) = &self.nrp {
+                    subclasses.extend(nrp.subclass.to_owned());
+                }
+            }
+            BiosyntheticClass::Polyketide => {
+                if let Some(polyketide) = &self.polyketide {
+                    subclasses.extend(polyketide.subclasses.to_owned().unwrap_or_default());
+                    for synthase in polyketide.synthases.iter().flatten() {
+                        subclasses.extend(synthase.subclasses.to_owned().unwrap_or_default());
+                    }
+                }
+            }
+            BiosyntheticClass::RiPP => {
+                if let Some(ripp) = &self.ripp {
+                    subclasses.extend(ripp.subclass.to_owned());
+                }
+            }
+            BiosyntheticClass::Saccharide => {
+                if let Some(saccharide) = &self.saccharide {
+                    subclasses.extend(saccharide.subclass.to_owned());
+                }
+            }
+            BiosyntheticClass::Other => {
+                if let Some(other) = &self.other {
+                    subclasses.push(other.subclass.to_owned());
+                }
+            }
+            BiosyntheticClass::Terpene => {}
+        }
+        subclasses.sort();
+        subclasses.dedup();
+        subclasses
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ChangeLog {
-    comments: Vec<String>,
-    contributors: Vec<String>,
-    version: String,
+    pub comments: Vec<String>,
+    pub contributors: Vec<String>,
+    pub version: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -104,14 +166,14 @@ pub enum LociEvidence {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Loci {
-    accession: String,
-    completeness: LociCompleteness,
+    pub accession: String,
+    pub completeness: LociCompleteness,
     #[serde(skip_serializing_if = "Option::is_none")]
-    end_coord: Option<u64>,
+    pub end_coord: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    evidence: Option<Vec<LociEvidence>>,
+    pub evidence: Option<Vec<LociEvidence>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    mixs_compliant: Option<bool>,
+    pub mixs_compliant: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    start_coord: Option<u64>,
+    pub start_coord: Option<u64>,
 }
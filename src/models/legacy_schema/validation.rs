@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::Serialize;
+
+use super::{BiosyntheticClass, Cluster, Entry};
+
+const ALL_CLASSES: [BiosyntheticClass; 7] = [
+    BiosyntheticClass::Alkaloid,
+    BiosyntheticClass::NRP,
+    BiosyntheticClass::Polyketide,
+    BiosyntheticClass::RiPP,
+    BiosyntheticClass::Saccharide,
+    BiosyntheticClass::Terpene,
+    BiosyntheticClass::Other,
+];
+
+/// A semantic rule an entry breaks, even though it matches the schema structure
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Violation {
+    /// Location of the offending value in the JSON document
+    pub path: String,
+    pub message: String,
+}
+
+impl Violation {
+    pub fn new(path: impl ToString, message: impl ToString) -> Self {
+        Violation {
+            path: path.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Check the rules serde can't express, collecting every violation instead of stopping at the first
+pub fn validate(entry: &Entry) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    check_accession(&entry.cluster, &mut violations);
+    check_coordinates(&entry.cluster, &mut violations);
+    check_gene_references(&entry.cluster, &mut violations);
+    check_classes(&entry.cluster, &mut violations);
+    violations
+}
+
+pub fn is_valid_accession(accession: &str) -> bool {
+    match accession.strip_prefix("BGC") {
+        Some(number) => number.len() == 7 && number.chars().all(|c| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+fn check_accession(cluster: &Cluster, violations: &mut Vec<Violation>) {
+    if !is_valid_accession(&cluster.mibig_accession) {
+        violations.push(Violation::new(
+            "cluster.mibig_accession",
+            format!(
+                "{:?} is not a valid MIBiG accession like BGC0000001",
+                cluster.mibig_accession
+            ),
+        ));
+    }
+}
+
+fn check_coordinates(cluster: &Cluster, violations: &mut Vec<Violation>) {
+    if let (Some(start), Some(end)) = (cluster.loci.start_coord, cluster.loci.end_coord) {
+        if start >= end {
+            violations.push(Violation::new(
+                "cluster.loci.start_coord",
+                format!("start {} is not before end {}", start, end),
+            ));
+        }
+    }
+}
+
+fn check_gene_references(cluster: &Cluster, violations: &mut Vec<Violation>) {
+    let mut known: HashSet<&str> = HashSet::new();
+    if let Some(genes) = &cluster.genes {
+        known.extend(
+            genes
+                .annotations
+                .iter()
+                .flatten()
+                .map(|gene| gene.id.as_str()),
+        );
+        known.extend(
+            genes
+                .extra_genes
+                .iter()
+                .flatten()
+                .map(|gene| gene.id.as_str()),
+        );
+    }
+
+    let mut check = |path: String, id: &str| {
+        if !known.contains(id) {
+            violations.push(Violation::new(
+                path,
+                format!(
+                    "gene {:?} is not in the gene annotations or extra genes",
+                    id
+                ),
+            ));
+        }
+    };
+
+    if let Some(genes) = &cluster.genes {
+        for (i, operon) in genes.operons.iter().flatten().enumerate() {
+            for (j, id) in operon.genes.iter().enumerate() {
+                check(format!("cluster.genes.operons[{}].genes[{}]", i, j), id);
+            }
+        }
+    }
+
+    if let Some(nrp) = &cluster.nrp {
+        for (i, gene) in nrp.nrps_genes.iter().flatten().enumerate() {
+            check(format!("cluster.nrp.nrps_genes[{}].id", i), &gene.id);
+        }
+    }
+}
+
+/// Name of the class-specific block and whether the cluster has it
+fn class_block(cluster: &Cluster, class: BiosyntheticClass) -> (&'static str, bool) {
+    match class {
+        BiosyntheticClass::Alkaloid => ("alkaloid", cluster.alkaloid.is_some()),
+        BiosyntheticClass::NRP => ("nrp", cluster.nrp.is_some()),
+        BiosyntheticClass::Polyketide => ("polyketide", cluster.polyketide.is_some()),
+        BiosyntheticClass::RiPP => ("ripp", cluster.ripp.is_some()),
+        BiosyntheticClass::Saccharide => ("saccharide", cluster.saccharide.is_some()),
+        BiosyntheticClass::Terpene => ("terpene", cluster.terpene.is_some()),
+        BiosyntheticClass::Other => ("other", cluster.other.is_some()),
+    }
+}
+
+/// Class blocks need a matching `biosyn_class`, and full entries need a block for every class.
+fn check_classes(cluster: &Cluster, violations: &mut Vec<Violation>) {
+    let listed: HashSet<&str> = cluster
+        .biosyn_class
+        .iter()
+        .map(|class| class_block(cluster, *class).0)
+        .collect();
+
+    for (i, class) in cluster.biosyn_class.iter().enumerate() {
+        let (block, present) = class_block(cluster, *class);
+        if !present && !cluster.minimal {
+            violations.push(Violation::new(
+                format!("cluster.biosyn_class[{}]", i),
+                format!("{:?} is listed but cluster.{} is missing", class, block),
+            ));
+        }
+    }
+
+    for class in ALL_CLASSES.iter() {
+        let (block, present) = class_block(cluster, *class);
+        if present && !listed.contains(block) {
+            violations.push(Violation::new(
+                format!("cluster.{}", block),
+                format!("{:?} data is present but not listed in biosyn_class", class),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Violations of a minimal NRP cluster, with `fields` replacing its defaults
+    fn violations_with(fields: serde_json::Value) -> Vec<Violation> {
+        let mut value = json!({
+            "biosyn_class": ["NRP"],
+            "compounds": [],
+            "loci": {"accession": "AB000001", "completeness": "complete"},
+            "mibig_accession": "BGC0000001",
+            "minimal": true,
+            "ncbi_tax_id": "1909",
+            "organism_name": "Streptomyces roseosporus",
+            "publications": [],
+            "nrp": {},
+        });
+        for (key, field) in fields.as_object().unwrap() {
+            value[key] = field.clone();
+        }
+        let entry = Entry {
+            changelog: Vec::new(),
+            cluster: serde_json::from_value(value).expect("test cluster should parse"),
+            comments: None,
+        };
+        validate(&entry)
+    }
+
+    fn paths(violations: &[Violation]) -> Vec<&str> {
+        violations.iter().map(|v| v.path.as_str()).collect()
+    }
+
+    #[test]
+    fn accession_needs_bgc_and_seven_digits() {
+        assert!(violations_with(json!({})).is_empty());
+
+        let violations = violations_with(json!({"mibig_accession": "BGC12345"}));
+        assert_eq!(paths(&violations), vec!["cluster.mibig_accession"]);
+    }
+
+    #[test]
+    fn loci_start_must_be_before_end() {
+        let loci = |start: u64, end: u64| {
+            json!({"loci": {
+                "accession": "AB000001",
+                "completeness": "complete",
+                "start_coord": start,
+                "end_coord": end,
+            }})
+        };
+        assert!(violations_with(loci(1, 5000)).is_empty());
+
+        let violations = violations_with(loci(5000, 5000));
+        assert_eq!(paths(&violations), vec!["cluster.loci.start_coord"]);
+    }
+
+    #[test]
+    fn gene_references_may_point_at_annotations_or_extra_genes() {
+        let genes = json!({
+            "annotations": [{"id": "dptA"}],
+            "extra_genes": [{"id": "orf1"}],
+            "operons": [{"evidence": [], "genes": ["dptA", "orf1"]}],
+        });
+        let valid = violations_with(json!({
+            "genes": genes,
+            "nrp": {"nrps_genes": [{"gene_id": "dptA"}, {"gene_id": "orf1"}]},
+        }));
+        assert!(valid.is_empty());
+
+        let invalid = violations_with(json!({
+            "genes": genes,
+            "nrp": {"nrps_genes": [{"gene_id": "dptA"}, {"gene_id": "dptX"}]},
+        }));
+        assert_eq!(paths(&invalid), vec!["cluster.nrp.nrps_genes[1].id"]);
+    }
+
+    #[test]
+    fn class_blocks_must_match_biosyn_class() {
+        let complete = violations_with(json!({"minimal": false}));
+        assert!(complete.is_empty());
+
+        let invalid = violations_with(json!({
+            "minimal": false,
+            "biosyn_class": ["NRP", "Polyketide"],
+            "terpene": {},
+        }));
+        assert_eq!(
+            paths(&invalid),
+            vec!["cluster.biosyn_class[1]", "cluster.terpene"]
+        );
+    }
+}
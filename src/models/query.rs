@@ -0,0 +1,526 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+use diesel::pg::{Pg, PgConnection};
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+
+use crate::errors::MibigError;
+use crate::models::db::bgc_types::BgcType;
+use crate::models::db::taxa::TaxonRank;
+use crate::schema::{compounds, entries, rel_entries_types, taxa};
+
+/// A compiled query, usable as a filter on `entries`
+pub type EntryFilter = Box<dyn BoxableExpression<entries::table, Pg, SqlType = Bool>>;
+
+const FIELDS: &str = "type, superkingdom, kingdom, phylum, class, order, family, genus, species, \
+                      taxid, organism, compound, accession";
+
+/// A boolean query over repository entries, like `type:transatpks AND genus:Streptomyces AND NOT minimal`.
+///
+/// Terms are `field:value` pairs or the `minimal` and `needs_review` flags, combined with
+/// `AND`, `OR`, `NOT` and parentheses. Neighbouring terms without an operator are ANDed.
+/// Values are case-insensitive and may use `*` as a wildcard, or be quoted to contain spaces.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Condition(Condition),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Type(String),
+    Rank(TaxonRank, String),
+    TaxId(i64),
+    Organism(String),
+    Compound(String),
+    Accession(String),
+    Minimal,
+    NeedsReview,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(Option<String>, String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::Term(Some(field), value) => write!(f, "'{}:{}'", field, value),
+            Token::Term(None, value) => write!(f, "'{}'", value),
+        }
+    }
+}
+
+fn invalid(message: String) -> MibigError {
+    MibigError::InvalidQuery(message)
+}
+
+/// Read a double-quoted value, the opening quote is already consumed
+fn read_quoted(chars: &mut Peekable<CharIndices>, start: usize) -> Result<String, MibigError> {
+    let mut value = String::new();
+    for (_, c) in chars.by_ref() {
+        if c == '"' {
+            return Ok(value);
+        }
+        value.push(c);
+    }
+    Err(invalid(format!("unterminated quote at position {}", start)))
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, MibigError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(pos, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            chars.next();
+            let token = if c == '(' {
+                Token::LParen
+            } else {
+                Token::RParen
+            };
+            tokens.push((pos, token));
+            continue;
+        }
+        if c == '"' {
+            chars.next();
+            let value = read_quoted(&mut chars, pos)?;
+            tokens.push((pos, Token::Term(None, value)));
+            continue;
+        }
+
+        let mut word = String::new();
+        let mut field = None;
+        while let Some(&(_, c)) = chars.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' {
+                break;
+            }
+            chars.next();
+            if c == ':' && field.is_none() {
+                field = Some(std::mem::take(&mut word));
+                if let Some(&(quote_pos, '"')) = chars.peek() {
+                    chars.next();
+                    word = read_quoted(&mut chars, quote_pos)?;
+                    break;
+                }
+                continue;
+            }
+            word.push(c);
+        }
+
+        let token = match (&field, word.to_uppercase().as_str()) {
+            (None, "AND") => Token::And,
+            (None, "OR") => Token::Or,
+            (None, "NOT") => Token::Not,
+            _ => Token::Term(field, word),
+        };
+        tokens.push((pos, token));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<(usize, Token)> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Query, MibigError> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            let right = self.parse_and()?;
+            left = Query::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, MibigError> {
+        let mut left = self.parse_not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Not) | Some(Token::LParen) | Some(Token::Term(_, _)) => {}
+                _ => break,
+            }
+            let right = self.parse_not()?;
+            left = Query::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Query, MibigError> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(Query::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Query, MibigError> {
+        match self.next() {
+            Some((pos, Token::LParen)) => {
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some((_, Token::RParen)) => Ok(inner),
+                    Some((found, token)) => Err(invalid(format!(
+                        "expected ')' to close the '(' at position {}, found {} at position {}",
+                        pos, token, found
+                    ))),
+                    None => Err(invalid(format!(
+                        "missing ')' for the '(' at position {}",
+                        pos
+                    ))),
+                }
+            }
+            Some((pos, Token::Term(field, value))) => {
+                Ok(Query::Condition(condition(pos, field, value)?))
+            }
+            Some((pos, token)) => Err(invalid(format!(
+                "expected a search term at position {}, found {}",
+                pos, token
+            ))),
+            None => Err(invalid("unexpected end of query".to_string())),
+        }
+    }
+}
+
+fn condition(pos: usize, field: Option<String>, value: String) -> Result<Condition, MibigError> {
+    let field = match field {
+        Some(field) => field.to_lowercase(),
+        None => {
+            return match value.to_lowercase().as_str() {
+                "minimal" => Ok(Condition::Minimal),
+                "needs_review" => Ok(Condition::NeedsReview),
+                _ => Err(invalid(format!(
+                    "expected field:value at position {}, found '{}' (fields are {})",
+                    pos, value, FIELDS
+                ))),
+            };
+        }
+    };
+
+    if value.is_empty() {
+        return Err(invalid(format!(
+            "missing value for '{}' at position {}",
+            field, pos
+        )));
+    }
+
+    let rank = match field.as_str() {
+        "type" => return Ok(Condition::Type(value)),
+        "taxid" => {
+            return value.parse().map(Condition::TaxId).map_err(|_| {
+                invalid(format!(
+                    "taxid must be a number, found '{}' at position {}",
+                    value, pos
+                ))
+            })
+        }
+        "organism" => return Ok(Condition::Organism(value)),
+        "compound" => return Ok(Condition::Compound(value)),
+        "accession" => return Ok(Condition::Accession(value)),
+        "superkingdom" => TaxonRank::Superkingdom,
+        "kingdom" => TaxonRank::Kingdom,
+        "phylum" => TaxonRank::Phylum,
+        "class" => TaxonRank::Class,
+        "order" => TaxonRank::Order,
+        "family" => TaxonRank::Family,
+        "genus" => TaxonRank::Genus,
+        "species" => TaxonRank::Species,
+        _ => {
+            return Err(invalid(format!(
+                "unknown field '{}' at position {} (fields are {})",
+                field, pos, FIELDS
+            )))
+        }
+    };
+    Ok(Condition::Rank(rank, value))
+}
+
+impl FromStr for Query {
+    type Err = MibigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        if parser.tokens.is_empty() {
+            return Err(invalid("empty query".to_string()));
+        }
+        let query = parser.parse_or()?;
+        match parser.next() {
+            None => Ok(query),
+            Some((pos, token)) => Err(invalid(format!("unexpected {} at position {}", token, pos))),
+        }
+    }
+}
+
+/// Turn a value into an ILIKE pattern, where only `*` is a wildcard
+fn pattern(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+        .replace('*', "%")
+}
+
+impl Query {
+    /// Compile the query into a filter on `entries`, resolving BGC types against the database
+    pub fn compile(&self, conn: &PgConnection) -> Result<EntryFilter, MibigError> {
+        let types = BgcType::all(conn)?;
+        self.to_filter(&types)
+    }
+
+    fn to_filter(&self, types: &[BgcType]) -> Result<EntryFilter, MibigError> {
+        let filter: EntryFilter = match self {
+            Query::Condition(condition) => condition.to_filter(types)?,
+            Query::Not(inner) => Box::new(diesel::dsl::not(inner.to_filter(types)?)),
+            Query::And(left, right) => {
+                Box::new(left.to_filter(types)?.and(right.to_filter(types)?))
+            }
+            Query::Or(left, right) => Box::new(left.to_filter(types)?.or(right.to_filter(types)?)),
+        };
+        Ok(filter)
+    }
+}
+
+impl Condition {
+    fn to_filter(&self, types: &[BgcType]) -> Result<EntryFilter, MibigError> {
+        let filter: EntryFilter = match self {
+            Condition::Type(term) => {
+                // A type also matches entries of its subtypes, like the type counts in the stats
+                let bgc_type = types
+                    .iter()
+                    .find(|t| t.term.eq_ignore_ascii_case(term))
+                    .ok_or_else(|| invalid(format!("unknown BGC type '{}'", term)))?;
+                let ids = bgc_type.descendant_ids(types);
+                Box::new(
+                    entries::id.eq_any(
+                        rel_entries_types::table
+                            .filter(rel_entries_types::bgc_type_id.eq_any(ids))
+                            .select(rel_entries_types::entry_id),
+                    ),
+                )
+            }
+            Condition::Rank(rank, value) => {
+                let pattern = pattern(value);
+                let lineages = taxa::table.select(taxa::tax_id.nullable()).into_boxed();
+                let lineages = match rank {
+                    TaxonRank::Superkingdom => lineages.filter(taxa::superkingdom.ilike(pattern)),
+                    TaxonRank::Kingdom => lineages.filter(taxa::kingdom.ilike(pattern)),
+                    TaxonRank::Phylum => lineages.filter(taxa::phylum.ilike(pattern)),
+                    TaxonRank::Class => lineages.filter(taxa::class.ilike(pattern)),
+                    TaxonRank::Order => lineages.filter(taxa::taxonomic_order.ilike(pattern)),
+                    TaxonRank::Family => lineages.filter(taxa::family.ilike(pattern)),
+                    TaxonRank::Genus => lineages.filter(taxa::genus.ilike(pattern)),
+                    TaxonRank::Species => lineages.filter(taxa::species.ilike(pattern)),
+                };
+                Box::new(entries::tax_id.eq_any(lineages))
+            }
+            Condition::TaxId(taxid) => Box::new(
+                entries::tax_id.eq_any(
+                    taxa::table
+                        .filter(taxa::ncbi_taxid.eq(*taxid))
+                        .select(taxa::tax_id.nullable()),
+                ),
+            ),
+            Condition::Organism(name) => Box::new(entries::organism_name.ilike(pattern(name))),
+            Condition::Compound(name) => Box::new(
+                entries::id.eq_any(
+                    compounds::table
+                        .filter(compounds::name.ilike(pattern(name)))
+                        .select(compounds::entry_id),
+                ),
+            ),
+            Condition::Accession(accession) => Box::new(entries::id.ilike(pattern(accession))),
+            Condition::Minimal => Box::new(entries::minimal.eq(true)),
+            Condition::NeedsReview => Box::new(entries::needs_review.eq(true)),
+        };
+        Ok(filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Query {
+        input.parse().expect("query should parse")
+    }
+
+    fn error(input: &str) -> String {
+        input
+            .parse::<Query>()
+            .expect_err("query should not parse")
+            .to_string()
+    }
+
+    fn cond(condition: Condition) -> Box<Query> {
+        Box::new(Query::Condition(condition))
+    }
+
+    fn genus(name: &str) -> Condition {
+        Condition::Rank(TaxonRank::Genus, name.to_string())
+    }
+
+    fn bgc_type(term: &str) -> Condition {
+        Condition::Type(term.to_string())
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse("type:nrps OR type:pks AND genus:Streptomyces"),
+            Query::Or(
+                cond(bgc_type("nrps")),
+                Box::new(Query::And(
+                    cond(bgc_type("pks")),
+                    cond(genus("Streptomyces"))
+                )),
+            )
+        );
+    }
+
+    #[test]
+    fn neighbouring_terms_are_anded() {
+        assert_eq!(
+            parse("type:nrps minimal"),
+            Query::And(cond(bgc_type("nrps")), cond(Condition::Minimal))
+        );
+    }
+
+    #[test]
+    fn parentheses_group() {
+        assert_eq!(
+            parse("(type:nrps OR type:pks) AND genus:Streptomyces"),
+            Query::And(
+                Box::new(Query::Or(cond(bgc_type("nrps")), cond(bgc_type("pks")))),
+                cond(genus("Streptomyces")),
+            )
+        );
+    }
+
+    #[test]
+    fn not_applies_to_the_next_term() {
+        assert_eq!(
+            parse("NOT minimal AND taxid:1909"),
+            Query::And(
+                Box::new(Query::Not(cond(Condition::Minimal))),
+                cond(Condition::TaxId(1909)),
+            )
+        );
+        assert_eq!(
+            parse("not not needs_review"),
+            Query::Not(Box::new(Query::Not(cond(Condition::NeedsReview))))
+        );
+    }
+
+    #[test]
+    fn quoted_values_keep_spaces() {
+        assert_eq!(
+            parse(r#"organism:"Streptomyces roseosporus*""#),
+            Query::Condition(Condition::Organism("Streptomyces roseosporus*".to_string()))
+        );
+        assert_eq!(
+            parse(r#"compound:daptomycin "minimal""#),
+            Query::And(
+                cond(Condition::Compound("daptomycin".to_string())),
+                cond(Condition::Minimal),
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert_eq!(
+            error("colour:blue"),
+            format!(
+                "Invalid query: unknown field 'colour' at position 0 (fields are {})",
+                FIELDS
+            )
+        );
+        assert_eq!(
+            error("type:nrps daptomycin"),
+            format!(
+                "Invalid query: expected field:value at position 10, found 'daptomycin' (fields are {})",
+                FIELDS
+            )
+        );
+        assert_eq!(
+            error("taxid:strep"),
+            "Invalid query: taxid must be a number, found 'strep' at position 0"
+        );
+        assert_eq!(
+            error("genus:"),
+            "Invalid query: missing value for 'genus' at position 0"
+        );
+    }
+
+    #[test]
+    fn unbalanced_input_is_rejected() {
+        assert_eq!(
+            error("(type:nrps OR minimal"),
+            "Invalid query: missing ')' for the '(' at position 0"
+        );
+        assert_eq!(
+            error("type:nrps)"),
+            "Invalid query: unexpected ')' at position 9"
+        );
+        assert_eq!(
+            error(r#"organism:"Streptomyces"#),
+            "Invalid query: unterminated quote at position 9"
+        );
+        assert_eq!(
+            error("minimal AND"),
+            "Invalid query: unexpected end of query"
+        );
+        assert_eq!(
+            error("OR minimal"),
+            "Invalid query: expected a search term at position 0, found OR"
+        );
+        assert_eq!(error("  "), "Invalid query: empty query");
+    }
+
+    #[test]
+    fn unknown_types_fail_to_compile() {
+        let err = parse("type:nope")
+            .to_filter(&[])
+            .err()
+            .expect("unknown type should not compile");
+        assert_eq!(err.to_string(), "Invalid query: unknown BGC type 'nope'");
+    }
+}
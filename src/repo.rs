@@ -1,18 +1,22 @@
-use std::path::PathBuf;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process;
 
-use serde_json;
 use structopt::clap::arg_enum;
 use structopt::StructOpt;
 
 use diesel::pg::PgConnection;
-use diesel::prelude::*;
-
-use mibig_taxa::TaxonCache;
 
 use crate::errors::MibigError;
+use crate::models::db::entries::EntryStatus;
 use crate::models::db::Entry;
+use crate::models::query::{Condition, Query};
 use crate::utils;
 
+mod export;
+mod import;
+mod validate;
+
 #[derive(Debug, StructOpt)]
 pub struct RepoOpts {
     #[structopt(subcommand)]
@@ -25,13 +29,33 @@ enum RepoSubcommand {
     List(RepoListOpts),
 
     #[structopt(name = "import", about = "Import a new entry")]
-    Import(RepoImportOpts),
+    Import(import::RepoImportOpts),
+
+    #[structopt(name = "status", about = "Change the status of an entry")]
+    Status(RepoStatusOpts),
+
+    #[structopt(name = "export", about = "Export entries as MIBiG JSON")]
+    Export(export::RepoExportOpts),
+
+    #[structopt(
+        name = "validate",
+        about = "Check MIBiG JSON files for semantic errors"
+    )]
+    Validate(validate::RepoValidateOpts),
 }
 
 #[derive(Debug, StructOpt)]
 pub struct RepoListOpts {
     #[structopt(short, long, help = "Status of the entry", possible_values = &RepoListEntryStatus::variants(), case_insensitive = true, default_value = "Published")]
     status: RepoListEntryStatus,
+    #[structopt(
+        short,
+        long,
+        help = "Only list entries matching a query, like 'type:nrps AND NOT minimal'"
+    )]
+    query: Option<String>,
+    #[structopt(long, help = "Only list entries flagged for curator review")]
+    needs_review: bool,
 }
 
 structopt::clap::arg_enum! {
@@ -44,181 +68,98 @@ structopt::clap::arg_enum! {
     }
 }
 
+impl From<RepoListEntryStatus> for EntryStatus {
+    fn from(status: RepoListEntryStatus) -> Self {
+        match status {
+            RepoListEntryStatus::Published => EntryStatus::Published,
+            RepoListEntryStatus::Retired => EntryStatus::Retired,
+            RepoListEntryStatus::Embargoed => EntryStatus::Embargoed,
+            RepoListEntryStatus::Reserved => EntryStatus::Reserved,
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
-pub struct RepoImportOpts {
-    #[structopt(
-        parse(from_os_str),
-        help = "File or directory containing MIBiG JSON data"
-    )]
-    input: PathBuf,
-    #[structopt(short, long, help = "Only run json parsing")]
-    json_only: bool,
-    #[structopt(
-        short,
-        long,
-        parse(from_os_str),
-        help = "File containing the taxon cache"
-    )]
-    tax_cache_path: Option<PathBuf>,
+pub struct RepoStatusOpts {
+    #[structopt(help = "Accession of the entry to change")]
+    accession: String,
+    #[structopt(help = "New status of the entry", possible_values = &RepoListEntryStatus::variants(), case_insensitive = true)]
+    status: RepoListEntryStatus,
 }
 
 pub fn repo(cfg: RepoOpts) {
-    let conn = utils::db::establish_connection();
-
-    match cfg.cmd {
-        Some(cmd) => match cmd {
-            RepoSubcommand::List(opts) => repo_list(opts.status, conn),
-            RepoSubcommand::Import(opts) => {
-                repo_import(opts.input, opts.tax_cache_path, conn, opts.json_only)
-            }
-        },
-        None => repo_list(RepoListEntryStatus::Published, conn),
+    let cmd = cfg.cmd.unwrap_or(RepoSubcommand::List(RepoListOpts {
+        status: RepoListEntryStatus::Published,
+        query: None,
+        needs_review: false,
+    }));
+
+    // Only connect for the subcommands that use the database
+    let conn = utils::db::establish_connection;
+    match cmd {
+        RepoSubcommand::List(opts) => repo_list(opts, conn()),
+        RepoSubcommand::Import(opts) => import::repo_import(opts, conn()),
+        RepoSubcommand::Status(opts) => repo_status(opts, conn()),
+        RepoSubcommand::Export(opts) => export::repo_export(opts, conn()),
+        RepoSubcommand::Validate(opts) => validate::repo_validate(opts),
     }
 }
 
-use crate::models;
-
-fn repo_import(
-    input: PathBuf,
-    tax_cache_path_opt: Option<PathBuf>,
-    conn: PgConnection,
-    json_only: bool,
-) {
-    let tax_cache_path: PathBuf;
-    match tax_cache_path_opt {
-        Some(path) => tax_cache_path = path,
-        None => tax_cache_path = PathBuf::from("tax_cache.json"),
+fn repo_list(opts: RepoListOpts, conn: PgConnection) {
+    let mut query = match opts.query.map(|q| q.parse::<Query>()).transpose() {
+        Ok(query) => query,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR);
+        }
     };
 
-    let mut taxon_cache = TaxonCache::new();
-    if let Ok(num_entries) = taxon_cache.load_path(&tax_cache_path) {
-        eprintln!("Loaded {num_entries} taxon entries");
+    if opts.needs_review {
+        let flagged = Query::Condition(Condition::NeedsReview);
+        query = Some(match query {
+            Some(query) => Query::And(Box::new(query), Box::new(flagged)),
+            None => flagged,
+        });
     }
 
-    if input.is_file() {
-        import_file(input, &taxon_cache, &conn, json_only);
-        return;
-    }
-
-    let mut entries = std::fs::read_dir(input)
-        .expect("failed to read input")
-        .map(|res| res.map(|e| e.path()))
-        .collect::<Result<Vec<_>, std::io::Error>>()
-        .unwrap();
-
-    entries.sort();
-
-    for path in entries {
-        if "json" != path.extension().unwrap() {
-            continue;
+    match Entry::list_by_status(opts.status.into(), query.as_ref(), &conn) {
+        Ok(results) => {
+            for entry in results {
+                println!("{}: {:?}", entry.id, entry.biosyn_class);
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR);
         }
-        eprintln!("Loading {:?}", path);
-        import_file(path, &taxon_cache, &conn, json_only);
     }
 }
 
-fn import_file(
-    infile: std::path::PathBuf,
-    cache: &TaxonCache,
-    conn: &PgConnection,
-    json_only: bool,
-) {
-    let content = std::fs::read_to_string(&infile).expect("could not read file");
-    let entry: models::legacy_schema::Entry = serde_json::from_str(&content).unwrap();
-
-    if !json_only {
-        insert_into_db(&entry, cache, conn);
+fn repo_status(opts: RepoStatusOpts, conn: PgConnection) {
+    match Entry::set_status(&opts.accession, opts.status.into(), &conn) {
+        Ok(entry) => println!("{}: {}", entry.id, entry.status),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR);
+        }
     }
-
-    let return_value = serde_json::to_string_pretty(&entry).expect("failed to serialize");
-    println!("{}", return_value)
 }
 
-fn insert_into_db(entry: &models::legacy_schema::Entry, cache: &TaxonCache, conn: &PgConnection) {
-    let tax_id = get_or_create_taxid(
-        entry.cluster.organism_name.as_str(),
-        entry.cluster.ncbi_tax_id,
-        cache,
-        conn,
-    )
-    .expect("Error getting tax_id");
-
-    let new_entry = models::db::NewEntry {
-        id: entry.cluster.mibig_accession.to_owned(),
-        biosyn_class: entry
-            .cluster
-            .biosyn_class
-            .to_vec()
-            .iter()
-            .map(|cls| {
-                serde_json::to_string(&cls)
-                    .expect("failed to convert biosyn_class")
-                    .trim_matches('"')
-                    .to_string()
-            })
-            .collect(),
-        minimal: entry.cluster.minimal,
-        organism_name: entry.cluster.organism_name.to_string(),
-        tax_id: tax_id,
-        legacy_comment: entry.comments.to_owned(),
-    };
-
-    let created_entry: models::db::Entry = diesel::insert_into(crate::schema::entries::table)
-        .values(&new_entry)
-        .get_result(conn)
-        .expect("Error saving entry");
-
-    eprintln!("{:?}", created_entry);
-}
+/// The input file itself, or all JSON files in the input directory
+fn json_paths(input: &Path) -> Result<Vec<PathBuf>, MibigError> {
+    if input.is_file() {
+        return Ok(vec![input.to_owned()]);
+    }
 
-fn get_or_create_taxid<'a>(
-    organism_name: &'a str,
-    ncbi_tax_id: i64,
-    cache: &TaxonCache,
-    conn: &'a PgConnection,
-) -> Result<i64, MibigError> {
-    use crate::schema::taxa::dsl::*;
-
-    let loaded_taxid: i64 = match taxa
-        .filter(ncbi_taxid.eq(ncbi_tax_id))
-        .filter(name.eq(organism_name))
-        .select(tax_id)
-        .first(conn)
-    {
-        Ok(number) => number,
-        Err(_) => {
-            let tax_info = utils::taxa::entry_for_taxid(ncbi_tax_id, cache)?;
-            let new_tax_entry = models::db::NewTaxon {
-                ncbi_taxid: ncbi_tax_id,
-                superkingdom: tax_info.superkingdom,
-                kingdom: tax_info.kingdom,
-                phylum: tax_info.phylum,
-                class: tax_info.class,
-                taxonomic_order: tax_info.order,
-                family: tax_info.family,
-                genus: tax_info.genus,
-                species: tax_info.species,
-                name: organism_name.to_string(),
-            };
-            let created_entry: models::db::Taxon = diesel::insert_into(crate::schema::taxa::table)
-                .values(&new_tax_entry)
-                .get_result(conn)
-                .expect("Error saving new tax entry");
-
-            created_entry.tax_id
-        }
+    let read_error = |e: std::io::Error| {
+        MibigError::InvalidInput(format!("failed to read {}: {}", input.display(), e))
     };
-    Ok(loaded_taxid)
-}
-
-fn repo_list(status: RepoListEntryStatus, conn: PgConnection) {
-    use crate::schema::entries::dsl::*;
-
-    let results = entries
-        .load::<Entry>(&conn)
-        .expect("Error loading entries.");
-    println!("Listing stuff on level {:?}", status);
-    for entry in results {
-        println!("{}: {:?}", entry.id, entry.biosyn_class);
-    }
+    let mut paths = std::fs::read_dir(input)
+        .map_err(read_error)?
+        .map(|res| res.map(|e| e.path()))
+        .collect::<Result<Vec<_>, std::io::Error>>()
+        .map_err(read_error)?;
+    paths.retain(|path| path.extension() == Some(OsStr::new("json")));
+    paths.sort();
+    Ok(paths)
 }
@@ -0,0 +1,136 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use diesel::pg::PgConnection;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Deserialize;
+use structopt::clap::arg_enum;
+use structopt::StructOpt;
+
+use super::RepoListEntryStatus;
+use crate::errors::MibigError;
+use crate::models::db::Entry;
+use crate::models::legacy_schema;
+
+#[derive(Debug, StructOpt)]
+pub struct RepoExportOpts {
+    #[structopt(
+        parse(from_os_str),
+        help = "Directory or archive file to write the entries to"
+    )]
+    output: PathBuf,
+    #[structopt(short, long, help = "Output format", possible_values = &ExportFormat::variants(), case_insensitive = true, default_value = "Dir")]
+    format: ExportFormat,
+    #[structopt(short, long, number_of_values = 1, help = "Status of the entries to export", possible_values = &RepoListEntryStatus::variants(), case_insensitive = true, default_value = "Published")]
+    status: Vec<RepoListEntryStatus>,
+}
+
+arg_enum! {
+    #[derive(Debug, Clone, Copy)]
+    enum ExportFormat {
+        Dir,
+        Tar,
+        Jsonl,
+    }
+}
+
+pub fn repo_export(opts: RepoExportOpts, conn: PgConnection) {
+    let mut documents: Vec<(String, String)> = Vec::new();
+    let mut invalid = 0;
+    for status in opts.status {
+        let entries = match Entry::list_by_status(status.into(), None, &conn) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                process::exit(exitcode::DATAERR);
+            }
+        };
+        for entry in entries {
+            match document_for(&entry) {
+                Ok(Some(document)) => documents.push((entry.id, document)),
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("{}: {e}, not exported", entry.id);
+                    invalid += 1;
+                }
+            }
+        }
+    }
+    documents.sort();
+
+    let written = match opts.format {
+        ExportFormat::Dir => write_dir(&opts.output, &documents),
+        ExportFormat::Tar => write_tar(&opts.output, &documents),
+        ExportFormat::Jsonl => write_jsonl(&opts.output, &documents),
+    };
+
+    match written {
+        Ok(_) => eprintln!(
+            "Exported {} entries to {}, {} invalid entries left out",
+            documents.len(),
+            opts.output.display(),
+            invalid
+        ),
+        Err(e) => {
+            eprintln!("Error: failed to write {}: {e}", opts.output.display());
+            process::exit(exitcode::IOERR);
+        }
+    }
+
+    if invalid > 0 {
+        process::exit(exitcode::DATAERR);
+    }
+}
+
+/// Render the stored data of an entry as a MIBiG JSON document.
+///
+/// The data is written out as stored, the schema structs don't cover every field. It still
+/// has to parse as a MIBiG entry, so only valid entries leave the database.
+/// Entries without data, like reserved ones, have nothing to export.
+fn document_for(entry: &Entry) -> Result<Option<String>, MibigError> {
+    let data = match &entry.data {
+        Some(data) => data,
+        None => return Ok(None),
+    };
+    legacy_schema::Entry::deserialize(data).map_err(|e| MibigError::InvalidInput(e.to_string()))?;
+    let document =
+        serde_json::to_string(data).map_err(|e| MibigError::InvalidInput(e.to_string()))?;
+    Ok(Some(document))
+}
+
+fn write_dir(output: &Path, documents: &[(String, String)]) -> std::io::Result<()> {
+    fs::create_dir_all(output)?;
+    for (accession, document) in documents {
+        fs::write(output.join(format!("{}.json", accession)), document)?;
+    }
+    Ok(())
+}
+
+fn write_tar(output: &Path, documents: &[(String, String)]) -> std::io::Result<()> {
+    let encoder = GzEncoder::new(File::create(output)?, Compression::default());
+    let mut archive = tar::Builder::new(encoder);
+    for (accession, document) in documents {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(document.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive.append_data(
+            &mut header,
+            format!("{}.json", accession),
+            document.as_bytes(),
+        )?;
+    }
+    archive.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn write_jsonl(output: &Path, documents: &[(String, String)]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(output)?);
+    for (_, document) in documents {
+        writeln!(writer, "{}", document)?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,603 @@
+use std::path::{Path, PathBuf};
+use std::process;
+
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool};
+use indicatif::{ProgressBar, ProgressStyle};
+use mibig_taxa::TaxonCache;
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use structopt::clap::arg_enum;
+use structopt::StructOpt;
+
+use crate::errors::MibigError;
+use crate::models;
+use crate::models::db::bgc_types::BgcType;
+use crate::models::db::entries::EntryStatus;
+use crate::models::db::entry_revisions::EntryRevision;
+use crate::models::db::Entry;
+use crate::utils;
+
+#[derive(Debug, StructOpt)]
+pub struct RepoImportOpts {
+    #[structopt(
+        parse(from_os_str),
+        help = "File or directory containing MIBiG JSON data"
+    )]
+    input: PathBuf,
+    #[structopt(short, long, help = "Only run json parsing")]
+    json_only: bool,
+    #[structopt(
+        short,
+        long,
+        parse(from_os_str),
+        help = "File containing the taxon cache"
+    )]
+    tax_cache_path: Option<PathBuf>,
+    #[structopt(short, long, help = "Update entries that already exist")]
+    update: bool,
+    #[structopt(
+        short,
+        long,
+        help = "Only show which entries would be added, changed or left unchanged"
+    )]
+    dry_run: bool,
+    #[structopt(
+        short,
+        long,
+        parse(from_os_str),
+        help = "Write a JSON report of the import to this file"
+    )]
+    report: Option<PathBuf>,
+    #[structopt(
+        long,
+        help = "Number of worker threads and database connections (default: number of CPUs)"
+    )]
+    jobs: Option<usize>,
+    #[structopt(
+        long,
+        default_value = "50",
+        help = "Number of files each worker imports before taking a new database connection"
+    )]
+    batch_size: usize,
+    #[structopt(long, help = "What to do with entries whose taxid is not in the taxon cache", possible_values = &UnknownTaxid::variants(), case_insensitive = true, default_value = "Fail")]
+    unknown_taxid: UnknownTaxid,
+}
+
+arg_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum UnknownTaxid {
+        Fail,
+        Skip,
+        Unclassified,
+    }
+}
+
+/// What importing a file does to the entry it contains
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ImportAction {
+    Add,
+    Change,
+    Unchanged,
+    Skip,
+}
+
+impl ImportAction {
+    fn label(&self) -> &'static str {
+        match *self {
+            ImportAction::Add => "added",
+            ImportAction::Change => "changed",
+            ImportAction::Unchanged => "unchanged",
+            ImportAction::Skip => "skipped",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ImportFailure {
+    file: String,
+    /// Location of the offending value in the JSON document, if the data didn't match the schema
+    path: Option<String>,
+    error: String,
+}
+
+impl ImportFailure {
+    fn new(file: &Path, path: Option<String>, error: impl ToString) -> Self {
+        ImportFailure {
+            file: file.display().to_string(),
+            path,
+            error: error.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ImportReport {
+    added: usize,
+    changed: usize,
+    unchanged: usize,
+    skipped: usize,
+    failed: usize,
+    failures: Vec<ImportFailure>,
+}
+
+impl ImportReport {
+    fn record(&mut self, result: Result<ImportAction, ImportFailure>) {
+        match result {
+            Ok(ImportAction::Add) => self.added += 1,
+            Ok(ImportAction::Change) => self.changed += 1,
+            Ok(ImportAction::Unchanged) => self.unchanged += 1,
+            Ok(ImportAction::Skip) => self.skipped += 1,
+            Err(failure) => {
+                self.failed += 1;
+                self.failures.push(failure);
+            }
+        }
+    }
+}
+
+struct ParsedFile {
+    path: PathBuf,
+    data: Value,
+    entry: models::legacy_schema::Entry,
+}
+
+/// Outcome of importing one file, plus any warnings to show the user
+struct FileResult {
+    accession: Option<String>,
+    result: Result<ImportAction, ImportFailure>,
+    notes: Vec<String>,
+}
+
+/// The taxon an entry will be stored with, worked out without writing anything
+#[derive(Debug)]
+enum TaxonLookup {
+    /// A taxon that is already stored, possibly with a placeholder lineage
+    Stored(models::db::Taxon),
+    /// A lineage to store, filling in the placeholder taxon with this ID if there is one
+    New(models::db::NewTaxon, Option<i64>),
+}
+
+pub fn repo_import(opts: RepoImportOpts, conn: PgConnection) {
+    let tax_cache_path = opts
+        .tax_cache_path
+        .to_owned()
+        .unwrap_or_else(|| PathBuf::from("tax_cache.json"));
+
+    let mut taxon_cache = TaxonCache::new();
+    if let Ok(num_entries) = taxon_cache.load_path(&tax_cache_path) {
+        eprintln!("Loaded {num_entries} taxon entries");
+    }
+
+    let types = BgcType::all(&conn).expect("Error loading BGC types");
+
+    let paths = match super::json_paths(&opts.input) {
+        Ok(paths) => paths,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::NOINPUT);
+        }
+    };
+
+    let mut builder = rayon::ThreadPoolBuilder::new();
+    if let Some(jobs) = opts.jobs {
+        builder = builder.num_threads(jobs);
+    }
+    let workers = builder.build().expect("Error starting worker threads");
+
+    let progress = ProgressBar::new(paths.len() as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{msg:>10} [{bar:40}] {pos}/{len} ({eta})")
+            .expect("invalid progress bar template")
+            .progress_chars("=> "),
+    );
+    progress.set_message("parsing");
+
+    let parsed: Vec<Result<ParsedFile, ImportFailure>> = workers.install(|| {
+        paths
+            .par_iter()
+            .map(|path| {
+                let res = parse_file(path);
+                progress.inc(1);
+                res
+            })
+            .collect()
+    });
+
+    let mut report = ImportReport::default();
+    let mut files: Vec<ParsedFile> = Vec::new();
+    for res in parsed {
+        match res {
+            Ok(file) => files.push(file),
+            Err(failure) => report.record(Err(failure)),
+        }
+    }
+
+    if opts.json_only {
+        progress.finish_and_clear();
+        eprintln!(
+            "Parsed {} files, {} failed",
+            files.len() + report.failed,
+            report.failed
+        );
+        print_failures(&report);
+        finish(&opts, &report);
+        return;
+    }
+
+    let pool = utils::db::establish_pool(workers.current_num_threads() as u32);
+
+    progress.reset();
+    progress.set_length(files.len() as u64);
+    progress.set_message("importing");
+
+    let batch_size = opts.batch_size.max(1);
+    let results: Vec<FileResult> = workers.install(|| {
+        files
+            .par_chunks(batch_size)
+            .flat_map_iter(|batch| {
+                let res = import_batch(batch, &pool, &taxon_cache, &types, &opts);
+                progress.inc(batch.len() as u64);
+                for file_res in res.iter() {
+                    for note in file_res.notes.iter() {
+                        warn(&progress, note);
+                    }
+                }
+                res
+            })
+            .collect()
+    });
+    progress.finish_and_clear();
+
+    for file_res in results {
+        if opts.dry_run {
+            if let (Some(accession), Ok(action)) = (&file_res.accession, &file_res.result) {
+                println!("{}\t{}", accession, action.label());
+            }
+        }
+        report.record(file_res.result);
+    }
+
+    eprintln!(
+        "Import finished: {} added, {} changed, {} unchanged, {} skipped, {} failed",
+        report.added, report.changed, report.unchanged, report.skipped, report.failed
+    );
+    print_failures(&report);
+    finish(&opts, &report);
+}
+
+/// Show a warning above the progress bar, or on stderr when there is no bar to draw
+fn warn(progress: &ProgressBar, message: &str) {
+    if progress.is_hidden() {
+        eprintln!("{}", message);
+    } else {
+        progress.println(message);
+    }
+}
+
+fn print_failures(report: &ImportReport) {
+    for failure in report.failures.iter() {
+        match &failure.path {
+            Some(path) => eprintln!("  {} at {}: {}", failure.file, path, failure.error),
+            None => eprintln!("  {}: {}", failure.file, failure.error),
+        }
+    }
+}
+
+/// Write the report file if requested, and exit with an error if any file failed
+fn finish(opts: &RepoImportOpts, report: &ImportReport) {
+    if let Some(report_path) = &opts.report {
+        let written = serde_json::to_string_pretty(report)
+            .map_err(|e| e.to_string())
+            .and_then(|content| std::fs::write(report_path, content).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            eprintln!(
+                "Error: failed to write report {}: {e}",
+                report_path.display()
+            );
+            process::exit(exitcode::IOERR);
+        }
+    }
+
+    if report.failed > 0 {
+        process::exit(exitcode::DATAERR);
+    }
+}
+
+fn parse_file(infile: &Path) -> Result<ParsedFile, ImportFailure> {
+    let content =
+        std::fs::read_to_string(infile).map_err(|e| ImportFailure::new(infile, None, e))?;
+    let data: Value =
+        serde_json::from_str(&content).map_err(|e| ImportFailure::new(infile, None, e))?;
+    let entry: models::legacy_schema::Entry = serde_path_to_error::deserialize(&data)
+        .map_err(|e| ImportFailure::new(infile, Some(e.path().to_string()), e.inner()))?;
+    Ok(ParsedFile {
+        path: infile.to_owned(),
+        data,
+        entry,
+    })
+}
+
+/// Import a batch of files on one pooled connection.
+///
+/// Each file is stored in its own transaction, so a failing file leaves no trace and
+/// can't take the rest of the batch down with it.
+fn import_batch(
+    batch: &[ParsedFile],
+    pool: &Pool<ConnectionManager<PgConnection>>,
+    cache: &TaxonCache,
+    types: &[BgcType],
+    opts: &RepoImportOpts,
+) -> Vec<FileResult> {
+    let failed_batch = |error: String| {
+        batch
+            .iter()
+            .map(|file| FileResult {
+                accession: None,
+                result: Err(ImportFailure::new(&file.path, None, &error)),
+                notes: Vec::new(),
+            })
+            .collect()
+    };
+
+    let conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return failed_batch(e.to_string()),
+    };
+
+    batch
+        .iter()
+        .map(|file| import_file(file, cache, types, &conn, opts))
+        .collect()
+}
+
+fn import_file(
+    file: &ParsedFile,
+    cache: &TaxonCache,
+    types: &[BgcType],
+    conn: &PgConnection,
+    opts: &RepoImportOpts,
+) -> FileResult {
+    let accession = file.entry.cluster.mibig_accession.to_owned();
+    let mut notes: Vec<String> = Vec::new();
+
+    let result = classify(file, conn, opts).and_then(|action| {
+        match action {
+            ImportAction::Add | ImportAction::Change => {
+                let cluster = &file.entry.cluster;
+                let allow_unclassified = opts.unknown_taxid == UnknownTaxid::Unclassified;
+                let skip_unknown = opts.unknown_taxid == UnknownTaxid::Skip;
+                let taxon = match lookup_taxon(
+                    &cluster.organism_name,
+                    cluster.ncbi_tax_id,
+                    allow_unclassified,
+                    cache,
+                    conn,
+                ) {
+                    Ok(taxon) => taxon,
+                    Err(MibigError::InvalidTaxID(_)) if skip_unknown => {
+                        notes.push(format!(
+                            "{}: unknown taxid {}, skipped",
+                            accession, cluster.ncbi_tax_id
+                        ));
+                        return Ok(ImportAction::Skip);
+                    }
+                    Err(e) => return Err(ImportFailure::new(&file.path, None, e)),
+                };
+                if opts.dry_run {
+                    return Ok(action);
+                }
+                insert_into_db(&file.entry, &file.data, taxon, types, conn)
+                    .map(|mut file_notes| notes.append(&mut file_notes))
+                    .map_err(|e| ImportFailure::new(&file.path, None, e))?;
+            }
+            ImportAction::Skip => {
+                notes.push(format!(
+                    "{}: already exists, use --update to change it",
+                    accession
+                ));
+            }
+            ImportAction::Unchanged => {}
+        }
+        Ok(action)
+    });
+
+    FileResult {
+        accession: Some(accession),
+        result,
+        notes,
+    }
+}
+
+/// Work out what importing the file would do to the stored entry
+fn classify(
+    file: &ParsedFile,
+    conn: &PgConnection,
+    opts: &RepoImportOpts,
+) -> Result<ImportAction, ImportFailure> {
+    let stored = Entry::find(&file.entry.cluster.mibig_accession, conn)
+        .map_err(|e| ImportFailure::new(&file.path, None, e))?;
+    let action = match stored {
+        None => ImportAction::Add,
+        // Filling in a reserved accession is a new entry as far as the import is concerned
+        Some(Entry { data: None, .. }) => ImportAction::Add,
+        Some(Entry {
+            data: Some(stored_data),
+            ..
+        }) if stored_data == file.data => ImportAction::Unchanged,
+        Some(_) if opts.update => ImportAction::Change,
+        Some(_) => ImportAction::Skip,
+    };
+    Ok(action)
+}
+
+/// Store an entry with its revision, compounds and BGC types.
+///
+/// Returns warnings about data that could not be fully mapped.
+fn insert_into_db(
+    entry: &models::legacy_schema::Entry,
+    data: &Value,
+    taxon: TaxonLookup,
+    types: &[BgcType],
+    conn: &PgConnection,
+) -> Result<Vec<String>, MibigError> {
+    let mut notes: Vec<String> = Vec::new();
+
+    let biosyn_class = entry
+        .cluster
+        .biosyn_class
+        .iter()
+        .map(|cls| {
+            serde_json::to_string(&cls)
+                .map(|cls| cls.trim_matches('"').to_string())
+                .map_err(|e| MibigError::InvalidInput(e.to_string()))
+        })
+        .collect::<Result<Vec<String>, MibigError>>()?;
+
+    // An updated entry must never be left without the revision recording the update, and
+    // taxa are only stored along with an entry that uses them
+    conn.transaction::<_, MibigError, _>(|| {
+        let taxon = store_taxon(taxon, conn)?;
+        let needs_review = taxon.is_unclassified();
+        let new_entry = models::db::NewEntry {
+            id: entry.cluster.mibig_accession.to_owned(),
+            biosyn_class,
+            minimal: entry.cluster.minimal,
+            organism_name: entry.cluster.organism_name.to_string(),
+            tax_id: Some(taxon.tax_id),
+            legacy_comment: entry.comments.to_owned(),
+            data: Some(data.to_owned()),
+            status: EntryStatus::Published.to_string(),
+            needs_review,
+        };
+        let (created_entry, _) = Entry::save(&new_entry, conn)?;
+        if needs_review {
+            notes.push(format!(
+                "{}: unknown taxid {}, flagged for review",
+                created_entry.id, taxon.ncbi_taxid
+            ));
+        }
+
+        EntryRevision::add(&created_entry.id, entry.changelog.last(), data, conn)?;
+
+        let new_compounds: Vec<models::db::compounds::NewCompound> = entry
+            .cluster
+            .compounds
+            .iter()
+            .map(|compound| {
+                models::db::compounds::NewCompound::from_legacy(&created_entry.id, compound)
+            })
+            .collect();
+        models::db::compounds::Compound::replace_for_entry(
+            &created_entry.id,
+            &new_compounds,
+            conn,
+        )?;
+        models::db::entry_search::EntrySearch::replace_for_entry(&created_entry.id, entry, conn)?;
+
+        let (type_ids, unmatched) = BgcType::ids_for_entry(&entry.cluster, types);
+        for subclass in unmatched {
+            notes.push(format!(
+                "{}: no BGC type found for subclass {:?}",
+                created_entry.id, subclass
+            ));
+        }
+        BgcType::set_for_entry(&created_entry.id, &type_ids, conn)?;
+
+        Ok(())
+    })?;
+
+    Ok(notes)
+}
+
+/// Find the taxon of an organism, or work out its lineage from the taxon cache.
+///
+/// Unknown taxids are an `InvalidTaxID` error, unless `allow_unclassified` uses a
+/// placeholder lineage for them. Existing placeholders are filled in once the cache knows
+/// their taxid, and otherwise fall under the same rule.
+fn lookup_taxon(
+    organism_name: &str,
+    ncbi_tax_id: i64,
+    allow_unclassified: bool,
+    cache: &TaxonCache,
+    conn: &PgConnection,
+) -> Result<TaxonLookup, MibigError> {
+    use crate::schema::taxa::dsl::*;
+
+    let existing: Option<models::db::Taxon> = taxa
+        .filter(ncbi_taxid.eq(ncbi_tax_id))
+        .filter(name.eq(organism_name))
+        .first(conn)
+        .optional()?;
+    let placeholder = match existing {
+        Some(taxon) if !taxon.is_unclassified() => return Ok(TaxonLookup::Stored(taxon)),
+        placeholder => placeholder,
+    };
+
+    match utils::taxa::entry_for_taxid(ncbi_tax_id, cache) {
+        Ok(tax_info) => {
+            let new_taxon = models::db::NewTaxon {
+                ncbi_taxid: ncbi_tax_id,
+                superkingdom: tax_info.superkingdom,
+                kingdom: tax_info.kingdom,
+                phylum: tax_info.phylum,
+                class: tax_info.class,
+                taxonomic_order: tax_info.order,
+                family: tax_info.family,
+                genus: tax_info.genus,
+                species: tax_info.species,
+                name: organism_name.to_string(),
+            };
+            Ok(TaxonLookup::New(
+                new_taxon,
+                placeholder.map(|taxon| taxon.tax_id),
+            ))
+        }
+        Err(MibigError::InvalidTaxID(_)) if allow_unclassified => Ok(match placeholder {
+            Some(taxon) => TaxonLookup::Stored(taxon),
+            None => TaxonLookup::New(
+                models::db::NewTaxon::unclassified(ncbi_tax_id, organism_name),
+                None,
+            ),
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+/// Store the taxon from `lookup_taxon`, unflagging the entries of a filled in placeholder
+fn store_taxon(lookup: TaxonLookup, conn: &PgConnection) -> Result<models::db::Taxon, MibigError> {
+    use crate::schema::taxa::dsl::*;
+
+    match lookup {
+        TaxonLookup::Stored(taxon) => Ok(taxon),
+        TaxonLookup::New(new_taxon, Some(placeholder_id)) => {
+            let classified = diesel::update(taxa.find(placeholder_id))
+                .set(&new_taxon)
+                .get_result(conn)?;
+            Entry::clear_review_for_taxon(placeholder_id, conn)?;
+            Ok(classified)
+        }
+        TaxonLookup::New(new_taxon, None) => {
+            // Another worker may be storing the same taxon, in which case this waits for
+            // it to commit and uses its row
+            diesel::insert_into(taxa)
+                .values(&new_taxon)
+                .on_conflict(name)
+                .do_nothing()
+                .execute(conn)?;
+            taxa.filter(ncbi_taxid.eq(new_taxon.ncbi_taxid))
+                .filter(name.eq(&new_taxon.name))
+                .first(conn)
+                .optional()?
+                .ok_or_else(|| {
+                    MibigError::InvalidInput(format!(
+                        "taxon {:?} already exists with another taxid",
+                        new_taxon.name
+                    ))
+                })
+        }
+    }
+}
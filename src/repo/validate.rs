@@ -0,0 +1,61 @@
+use std::path::{Path, PathBuf};
+use std::process;
+
+use serde_json::Value;
+use structopt::StructOpt;
+
+use crate::models::legacy_schema;
+use crate::models::legacy_schema::validation::{self, Violation};
+
+#[derive(Debug, StructOpt)]
+pub struct RepoValidateOpts {
+    #[structopt(
+        parse(from_os_str),
+        help = "MIBiG JSON file or directory of files to check"
+    )]
+    input: PathBuf,
+}
+
+pub fn repo_validate(opts: RepoValidateOpts) {
+    let paths = match super::json_paths(&opts.input) {
+        Ok(paths) => paths,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::NOINPUT);
+        }
+    };
+
+    let mut invalid = 0;
+    for path in &paths {
+        let violations = check_file(path);
+        if violations.is_empty() {
+            continue;
+        }
+        invalid += 1;
+        for violation in violations {
+            println!("{}: {}", path.display(), violation);
+        }
+    }
+
+    eprintln!("Checked {} files, {} with violations", paths.len(), invalid);
+
+    if invalid > 0 {
+        process::exit(exitcode::DATAERR);
+    }
+}
+
+/// Everything wrong with a file, starting with problems that stop it from parsing at all
+fn check_file(path: &Path) -> Vec<Violation> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => return vec![Violation::new(".", e)],
+    };
+    let data: Value = match serde_json::from_str(&content) {
+        Ok(data) => data,
+        Err(e) => return vec![Violation::new(".", e)],
+    };
+    match serde_path_to_error::deserialize::<_, legacy_schema::Entry>(&data) {
+        Ok(entry) => validation::validate(&entry),
+        Err(e) => vec![Violation::new(e.path(), e.inner())],
+    }
+}
@@ -3,14 +3,50 @@ use exitcode;
 use std::process;
 use structopt::StructOpt;
 
+use crate::models::db::roles::{Role, CURATOR};
 use crate::models::db::submission_requests::SubmissionRequest;
+use crate::models::db::submitters::Submitter;
 
 #[derive(Debug, StructOpt)]
 pub struct ReqApproveOpts {
     #[structopt(help = "ID of request to approve")]
     id: i64,
+    #[structopt(help = "user ID or email of the curator approving the request")]
+    curator: String,
 }
 
-pub fn req_approve(opts: ReqApproveOpts, _conn: PgConnection) {
-    eprintln!("Accepting {id}", id = opts.id)
+pub fn req_approve(opts: ReqApproveOpts, conn: PgConnection) {
+    let curator_id: String;
+    if opts.curator.contains("@") {
+        match Submitter::get_id_by_email(opts.curator.clone().into(), &conn) {
+            Ok(val) => curator_id = val,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                process::exit(exitcode::DATAERR)
+            }
+        }
+    } else {
+        curator_id = opts.curator;
+    }
+
+    match Role::names_for_user(&curator_id, &conn) {
+        Ok(roles) => {
+            if !roles.iter().any(|r| r == CURATOR) {
+                eprintln!("Error: {curator_id} is not a curator");
+                process::exit(exitcode::NOPERM)
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR)
+        }
+    }
+
+    match SubmissionRequest::approve(opts.id, &curator_id, &conn) {
+        Ok(accession) => println!("Approved request {id} as {accession}", id = opts.id),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR)
+        }
+    }
 }
@@ -5,17 +5,20 @@ use std::process;
 use crate::models::db::submission_requests::SubmissionRequest;
 
 pub fn req_list(conn: PgConnection) {
-    println!("id\tuser\tcompounds\taccession\tstart\tend");
+    println!("id\tuser\tcompounds\taccession\tstart\tend\tstatus\tentry");
     match SubmissionRequest::list(&conn) {
         Ok(results) => {
             for request in results {
                 println!(
-                    "{id}\t{user}\t{compounds:?}\t{start:?}\t{end:?}",
+                    "{id}\t{user}\t{compounds:?}\t{accession}\t{start:?}\t{end:?}\t{status}\t{entry}",
                     id = request.id,
                     user = request.user_id,
                     compounds = request.compounds,
+                    accession = request.accession,
                     start = request.start_nt,
                     end = request.end_nt,
+                    status = request.status,
+                    entry = request.entry_id.unwrap_or_default(),
                 );
             }
         }
@@ -12,6 +12,25 @@ table! {
     }
 }
 
+table! {
+    use diesel::sql_types::*;
+    use crate::utils::typedefs::sql_types::*;
+
+    compounds (compound_id) {
+        compound_id -> Int8,
+        entry_id -> Text,
+        name -> Text,
+        synonyms -> Array<Text>,
+        formula -> Nullable<Text>,
+        mol_mass -> Nullable<Float8>,
+        structure -> Nullable<Text>,
+        activities -> Array<Text>,
+        targets -> Array<Text>,
+        database_ids -> Array<Text>,
+        evidences -> Array<Text>,
+    }
+}
+
 table! {
     use diesel::sql_types::*;
     use crate::utils::typedefs::sql_types::*;
@@ -19,10 +38,39 @@ table! {
     entries (id) {
         id -> Text,
         minimal -> Bool,
-        tax_id -> Int8,
+        tax_id -> Nullable<Int8>,
         organism_name -> Text,
         biosyn_class -> Array<Text>,
         legacy_comment -> Nullable<Text>,
+        data -> Nullable<Jsonb>,
+        status -> Text,
+        needs_review -> Bool,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::utils::typedefs::sql_types::*;
+
+    entry_revisions (revision_id) {
+        revision_id -> Int8,
+        entry_id -> Text,
+        revision -> Int4,
+        version -> Text,
+        contributors -> Array<Text>,
+        comments -> Array<Text>,
+        data -> Jsonb,
+        created -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::utils::typedefs::sql_types::*;
+
+    entry_search (entry_id) {
+        entry_id -> Text,
+        document -> Tsvector,
     }
 }
 
@@ -68,6 +116,25 @@ table! {
         accession -> Text,
         start_nt -> Nullable<Int4>,
         end_nt -> Nullable<Int4>,
+        status -> Text,
+        entry_id -> Nullable<Text>,
+        resolved_by -> Nullable<Text>,
+        resolved_at -> Nullable<Timestamptz>,
+        resolution_reason -> Nullable<Text>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::utils::typedefs::sql_types::*;
+
+    submissions (submission_id) {
+        submission_id -> Int8,
+        entry_id -> Text,
+        user_id -> Text,
+        data -> Jsonb,
+        created -> Timestamptz,
+        updated -> Timestamptz,
     }
 }
 
@@ -120,21 +187,30 @@ table! {
     }
 }
 
+joinable!(compounds -> entries (entry_id));
 joinable!(entries -> taxa (tax_id));
+joinable!(entry_revisions -> entries (entry_id));
+joinable!(entry_search -> entries (entry_id));
 joinable!(rel_entries_types -> bgc_types (bgc_type_id));
 joinable!(rel_entries_types -> entries (entry_id));
 joinable!(rel_submitters_roles -> roles (role_id));
 joinable!(rel_submitters_roles -> submitters (user_id));
-joinable!(submission_requests -> submitters (user_id));
+joinable!(submission_requests -> entries (entry_id));
+joinable!(submissions -> entries (entry_id));
+joinable!(submissions -> submitters (user_id));
 joinable!(tokens -> submitters (user_id));
 
 allow_tables_to_appear_in_same_query!(
     bgc_types,
+    compounds,
     entries,
+    entry_revisions,
+    entry_search,
     rel_entries_types,
     rel_submitters_roles,
     roles,
     submission_requests,
+    submissions,
     submitters,
     taxa,
     tokens,
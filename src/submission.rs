@@ -15,8 +15,8 @@ pub struct SubmissionOpts {
 
 #[derive(Debug, StructOpt)]
 enum SubmissionSubcommand {
-    #[structopt(name = "list", about = "List submission")]
-    List,
+    #[structopt(name = "list", about = "List submissions")]
+    List(list::ListSubmissionOpts),
     #[structopt(name = "create", about = "Create a submission")]
     Create(create::CreateSubmissionOpts),
     #[structopt(name = "delete", about = "Delete a submission")]
@@ -30,11 +30,11 @@ pub fn submission(cfg: SubmissionOpts) {
 
     match cfg.cmd {
         Some(cmd) => match cmd {
-            SubmissionSubcommand::List => list::submission_list(conn),
+            SubmissionSubcommand::List(opts) => list::submission_list(opts, conn),
             SubmissionSubcommand::Create(opts) => create::submission_create(opts, conn),
             SubmissionSubcommand::Edit(opts) => edit::submission_edit(opts, conn),
             SubmissionSubcommand::Delete(opts) => delete::submission_delete(opts, conn),
         },
-        None => list::user_list(conn),
+        None => list::submission_list(Default::default(), conn),
     }
 }
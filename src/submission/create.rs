@@ -0,0 +1,58 @@
+use diesel::pg::PgConnection;
+use exitcode;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use structopt::StructOpt;
+
+use crate::models::db::submission::Submission;
+use crate::models::db::submitters::Submitter;
+
+#[derive(Debug, StructOpt)]
+pub struct CreateSubmissionOpts {
+    #[structopt(help = "user ID or email of the user the draft belongs to")]
+    identifier: String,
+    #[structopt(parse(from_os_str), help = "MIBiG JSON file containing the draft")]
+    input: PathBuf,
+}
+
+pub fn submission_create(opts: CreateSubmissionOpts, conn: PgConnection) {
+    let user_id: String;
+    if opts.identifier.contains("@") {
+        match Submitter::get_id_by_email(opts.identifier.clone().into(), &conn) {
+            Ok(val) => user_id = val,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                process::exit(exitcode::DATAERR)
+            }
+        }
+    } else {
+        user_id = opts.identifier;
+    }
+
+    let data = match fs::read_to_string(&opts.input) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error: failed to parse {}: {e}", opts.input.display());
+                process::exit(exitcode::DATAERR)
+            }
+        },
+        Err(e) => {
+            eprintln!("Error: failed to read {}: {e}", opts.input.display());
+            process::exit(exitcode::NOINPUT)
+        }
+    };
+
+    match Submission::create(&user_id, data, &conn) {
+        Ok(submission) => println!(
+            "Created submission {id} for {entry}",
+            id = submission.submission_id,
+            entry = submission.entry_id
+        ),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR)
+        }
+    }
+}
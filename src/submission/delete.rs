@@ -0,0 +1,22 @@
+use diesel::pg::PgConnection;
+use exitcode;
+use std::process;
+use structopt::StructOpt;
+
+use crate::models::db::submission::Submission;
+
+#[derive(Debug, StructOpt)]
+pub struct DeleteSubmissionOpts {
+    #[structopt(help = "ID of the submission to delete")]
+    id: i64,
+}
+
+pub fn submission_delete(opts: DeleteSubmissionOpts, conn: PgConnection) {
+    match Submission::delete(opts.id, &conn) {
+        Ok(_) => println!("Deleted submission {id}.", id = opts.id),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR)
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use diesel::pg::PgConnection;
+use exitcode;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use structopt::StructOpt;
+
+use crate::models::db::submission::Submission;
+
+#[derive(Debug, StructOpt)]
+pub struct EditSubmissionOpts {
+    #[structopt(help = "ID of the submission to replace")]
+    id: i64,
+    #[structopt(parse(from_os_str), help = "MIBiG JSON file containing the new draft")]
+    input: PathBuf,
+}
+
+pub fn submission_edit(opts: EditSubmissionOpts, conn: PgConnection) {
+    let data = match fs::read_to_string(&opts.input) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error: failed to parse {}: {e}", opts.input.display());
+                process::exit(exitcode::DATAERR)
+            }
+        },
+        Err(e) => {
+            eprintln!("Error: failed to read {}: {e}", opts.input.display());
+            process::exit(exitcode::NOINPUT)
+        }
+    };
+
+    match Submission::update_data(opts.id, data, &conn) {
+        Ok(submission) => println!(
+            "Updated submission {id} for {entry}",
+            id = submission.submission_id,
+            entry = submission.entry_id
+        ),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR)
+        }
+    }
+}
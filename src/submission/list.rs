@@ -0,0 +1,54 @@
+use diesel::pg::PgConnection;
+use exitcode;
+use std::process;
+use structopt::StructOpt;
+
+use crate::models::db::submission::Submission;
+use crate::models::db::submitters::Submitter;
+
+#[derive(Debug, Default, StructOpt)]
+pub struct ListSubmissionOpts {
+    #[structopt(help = "Only list drafts of this user ID or email")]
+    identifier: Option<String>,
+}
+
+pub fn submission_list(opts: ListSubmissionOpts, conn: PgConnection) {
+    let submissions = match opts.identifier {
+        Some(identifier) => {
+            let user_id: String;
+            if identifier.contains("@") {
+                match Submitter::get_id_by_email(identifier.into(), &conn) {
+                    Ok(val) => user_id = val,
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        process::exit(exitcode::DATAERR)
+                    }
+                }
+            } else {
+                user_id = identifier;
+            }
+            Submission::list_for_user(&user_id, &conn)
+        }
+        None => Submission::list(&conn),
+    };
+
+    println!("id\tentry\tuser\tcreated\tupdated");
+    match submissions {
+        Ok(results) => {
+            for submission in results {
+                println!(
+                    "{id}\t{entry}\t{user}\t{created}\t{updated}",
+                    id = submission.submission_id,
+                    entry = submission.entry_id,
+                    user = submission.user_id,
+                    created = submission.created.format("%+"),
+                    updated = submission.updated.format("%+"),
+                );
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR);
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use structopt::StructOpt;
+
+use crate::utils;
+
+mod refresh;
+
+#[derive(Debug, StructOpt)]
+pub struct TaxaOpts {
+    #[structopt(subcommand)]
+    cmd: TaxaSubcommand,
+}
+
+#[derive(Debug, StructOpt)]
+enum TaxaSubcommand {
+    #[structopt(
+        name = "refresh",
+        about = "Rebuild the taxon cache and lineages from an NCBI taxdump"
+    )]
+    Refresh(refresh::TaxaRefreshOpts),
+}
+
+pub fn taxa(cfg: TaxaOpts) {
+    let conn = utils::db::establish_connection();
+
+    match cfg.cmd {
+        TaxaSubcommand::Refresh(opts) => refresh::taxa_refresh(opts, conn),
+    }
+}
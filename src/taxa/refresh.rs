@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use mibig_taxa::TaxonCache;
+use structopt::StructOpt;
+
+use crate::errors::MibigError;
+use crate::models::db::taxa::{Taxon, TaxonRank};
+use crate::models::db::Entry;
+use crate::utils;
+
+#[derive(Debug, StructOpt)]
+pub struct TaxaRefreshOpts {
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Directory with the names.dmp, nodes.dmp and merged.dmp files"
+    )]
+    taxdump: PathBuf,
+    #[structopt(
+        short,
+        long = "tax-cache",
+        parse(from_os_str),
+        help = "Taxon cache file to rebuild",
+        default_value = "tax_cache.json"
+    )]
+    tax_cache_path: PathBuf,
+    #[structopt(short, long, help = "Report changed lineages without saving anything")]
+    dry_run: bool,
+}
+
+pub fn taxa_refresh(opts: TaxaRefreshOpts, conn: PgConnection) {
+    if let Err(e) = refresh(&opts, &conn) {
+        eprintln!("Error: {e}");
+        match e {
+            MibigError::Io(_) => process::exit(exitcode::IOERR),
+            _ => process::exit(exitcode::DATAERR),
+        }
+    }
+}
+
+fn refresh(opts: &TaxaRefreshOpts, conn: &PgConnection) -> Result<(), MibigError> {
+    let rows = Taxon::all(conn)?;
+
+    // Keep every taxid the old cache knew about, even if no entry uses it yet
+    let mut old_cache = TaxonCache::new();
+    let _ = old_cache.load_path(&opts.tax_cache_path);
+    let mut taxids: HashSet<i64> = rows.iter().map(|row| row.ncbi_taxid).collect();
+    taxids.extend(old_cache.mappings.keys());
+    taxids.extend(old_cache.deprecated_ids.keys());
+
+    let cache = utils::taxa::cache_from_taxdump(&opts.taxdump, &taxids)?;
+    eprintln!(
+        "Resolved {} of {} taxids from {}",
+        cache.mappings.len(),
+        taxids.len(),
+        opts.taxdump.display()
+    );
+
+    let mut changed: Vec<Taxon> = Vec::new();
+    let mut unchanged = 0;
+    let mut unresolved = 0;
+    for row in rows {
+        let entry = match utils::taxa::entry_for_taxid(row.ncbi_taxid, &cache) {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("{}: {}, keeping the old lineage", row.name, e);
+                unresolved += 1;
+                continue;
+            }
+        };
+        let refreshed = Taxon {
+            tax_id: row.tax_id,
+            ncbi_taxid: row.ncbi_taxid,
+            superkingdom: entry.superkingdom,
+            kingdom: entry.kingdom,
+            phylum: entry.phylum,
+            class: entry.class,
+            taxonomic_order: entry.order,
+            family: entry.family,
+            genus: entry.genus,
+            species: entry.species,
+            name: row.name.to_owned(),
+        };
+        if refreshed == row {
+            unchanged += 1;
+            continue;
+        }
+
+        if entry.tax_id != row.ncbi_taxid {
+            println!(
+                "{} (taxid {}): merged into taxid {}",
+                row.name, row.ncbi_taxid, entry.tax_id
+            );
+        } else {
+            println!("{} (taxid {}):", row.name, row.ncbi_taxid);
+        }
+        for rank in TaxonRank::ALL.iter() {
+            let (before, after) = (row.at_rank(*rank), refreshed.at_rank(*rank));
+            if before != after {
+                println!("    {}: {} -> {}", rank, before, after);
+            }
+        }
+        changed.push(refreshed);
+    }
+
+    eprintln!(
+        "{} lineages changed, {} unchanged, {} unresolved",
+        changed.len(),
+        unchanged,
+        unresolved
+    );
+
+    if opts.dry_run {
+        return Ok(());
+    }
+
+    let reviewed = conn.transaction::<_, MibigError, _>(|| {
+        for taxon in &changed {
+            taxon.save(conn)?;
+        }
+        Entry::clear_review_for_classified(conn)
+    })?;
+    // Only replace the cache once the database agrees with it
+    cache.save_path(&opts.tax_cache_path).map_err(|e| {
+        MibigError::Io(io::Error::other(format!(
+            "failed to save {}: {}",
+            opts.tax_cache_path.display(),
+            e
+        )))
+    })?;
+    eprintln!(
+        "Saved {} taxon entries to {}",
+        cache.mappings.len(),
+        opts.tax_cache_path.display()
+    );
+    if reviewed > 0 {
+        eprintln!(
+            "{} entries with a newly classified taxon no longer need review",
+            reviewed
+        );
+    }
+
+    Ok(())
+}
@@ -19,6 +19,8 @@ pub struct TokenOpts {
 enum TokenSubcommand {
     #[structopt(name = "list", about = "List tokens")]
     List(TokenListOpts),
+    #[structopt(name = "purge", about = "Delete expired tokens")]
+    Purge,
 }
 
 #[derive(Debug, StructOpt)]
@@ -33,6 +35,7 @@ structopt::clap::arg_enum! {
         All,
         Activation,
         Authentication,
+        PasswordReset,
     }
 }
 
@@ -42,6 +45,7 @@ pub fn token(cfg: TokenOpts) {
     match cfg.cmd {
         Some(cmd) => match cmd {
             TokenSubcommand::List(opts) => token_list(opts.scope, conn),
+            TokenSubcommand::Purge => token_purge(conn),
         },
         None => token_list(TokenListScope::All, conn),
     }
@@ -73,3 +77,13 @@ fn token_list(scope: TokenListScope, conn: PgConnection) {
         }
     }
 }
+
+fn token_purge(conn: PgConnection) {
+    match Token::delete_expired(&conn) {
+        Ok(deleted) => eprintln!("Deleted {deleted} expired tokens"),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR);
+        }
+    }
+}
@@ -7,6 +7,7 @@ mod common;
 mod delete;
 mod edit;
 mod list;
+mod roles;
 
 #[derive(Debug, StructOpt)]
 pub struct UserOpts {
@@ -24,6 +25,8 @@ enum UserSubcommand {
     Delete(delete::UserDeleteOpts),
     #[structopt(name = "edit", about = "Edit a user")]
     Edit(edit::UserEditOpts),
+    #[structopt(name = "roles", about = "Show or set the roles of a user")]
+    Roles(roles::UserRolesOpts),
 }
 
 pub fn user(cfg: UserOpts) {
@@ -35,6 +38,7 @@ pub fn user(cfg: UserOpts) {
             UserSubcommand::Add(opts) => add::user_add(opts, conn),
             UserSubcommand::Edit(opts) => edit::user_edit(opts, conn),
             UserSubcommand::Delete(opts) => delete::user_delete(opts, conn),
+            UserSubcommand::Roles(opts) => roles::user_roles(opts, conn),
         },
         None => list::user_list(conn),
     }
@@ -0,0 +1,52 @@
+use diesel::pg::PgConnection;
+use exitcode;
+use std::process;
+use structopt::StructOpt;
+
+use crate::models::db::roles::Role;
+use crate::models::db::submitters::Submitter;
+
+#[derive(Debug, StructOpt)]
+pub struct UserRolesOpts {
+    #[structopt(help = "user ID or email of user to show or change roles for")]
+    identifier: String,
+    #[structopt(help = "New roles of the user (admin, curator, submitter)")]
+    roles: Vec<String>,
+    #[structopt(short, long, help = "Remove all roles from the user")]
+    clear: bool,
+}
+
+pub fn user_roles(opts: UserRolesOpts, conn: PgConnection) {
+    let id: String;
+    if opts.identifier.contains("@") {
+        match Submitter::get_id_by_email(opts.identifier.clone().into(), &conn) {
+            Ok(val) => id = val,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                process::exit(exitcode::DATAERR)
+            }
+        }
+    } else {
+        id = opts.identifier;
+    }
+
+    if let Err(e) = Submitter::show(&id, &conn) {
+        eprintln!("Error: {e}");
+        process::exit(exitcode::DATAERR)
+    }
+
+    if opts.clear || !opts.roles.is_empty() {
+        if let Err(e) = Role::set_for_user(&id, &opts.roles, &conn) {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR)
+        }
+    }
+
+    match Role::names_for_user(&id, &conn) {
+        Ok(roles) => println!("{id}: {}", roles.join(", ")),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(exitcode::DATAERR)
+        }
+    }
+}
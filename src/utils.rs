@@ -1,4 +1,5 @@
 pub mod db;
+pub mod mailer;
 pub mod num_as_string;
 pub mod taxa;
 pub mod typedefs;
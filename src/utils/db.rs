@@ -2,6 +2,7 @@ use std::env;
 
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool};
 use dotenv::dotenv;
 
 pub fn establish_connection() -> PgConnection {
@@ -11,3 +12,15 @@ pub fn establish_connection() -> PgConnection {
 
     PgConnection::establish(&database_url).expect(&format!("Error connecting to {}", database_url))
 }
+
+pub fn establish_pool(size: u32) -> Pool<ConnectionManager<PgConnection>> {
+    dotenv().ok();
+
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let manager = ConnectionManager::<PgConnection>::new(&database_url);
+
+    Pool::builder()
+        .max_size(size)
+        .build(manager)
+        .unwrap_or_else(|e| panic!("Error connecting to {}: {}", database_url, e))
+}
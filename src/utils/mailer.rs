@@ -0,0 +1,55 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::errors::MibigError;
+use crate::utils::generate_uid;
+
+/// Something that can deliver messages to users
+pub trait Mailer: Send + Sync {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), MibigError>;
+}
+
+/// Print messages to stderr instead of sending them
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), MibigError> {
+        eprintln!("To: {to}\nSubject: {subject}\n\n{body}");
+        Ok(())
+    }
+}
+
+/// Write each message to its own file in a directory
+pub struct FileMailer {
+    pub outdir: PathBuf,
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), MibigError> {
+        fs::create_dir_all(&self.outdir)?;
+        let filename = format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S"),
+            generate_uid(5)?
+        );
+        let message = format!("To: {to}\nSubject: {subject}\n\n{body}\n");
+        fs::write(self.outdir.join(filename), message)?;
+        Ok(())
+    }
+}
+
+/// Pick the mailer backend from the MIBIG_MAILER environment variable.
+///
+/// Use "file:<directory>" to write messages to files, anything else logs them.
+pub fn from_env() -> Arc<dyn Mailer> {
+    match env::var("MIBIG_MAILER") {
+        Ok(setting) if setting.starts_with("file:") => Arc::new(FileMailer {
+            outdir: PathBuf::from(setting.trim_start_matches("file:")),
+        }),
+        _ => Arc::new(LogMailer),
+    }
+}
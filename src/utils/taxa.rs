@@ -1,6 +1,12 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
 use mibig_taxa::{NcbiTaxEntry, TaxonCache};
 
 use crate::errors::MibigError;
+use crate::models::db::taxa::TaxonRank;
 
 pub fn entry_for_taxid(tax_id: i64, cache: &TaxonCache) -> Result<NcbiTaxEntry, MibigError> {
     if let Some(entry) = cache.mappings.get(&tax_id) {
@@ -18,3 +24,276 @@ pub fn entry_for_taxid(tax_id: i64, cache: &TaxonCache) -> Result<NcbiTaxEntry,
         tax_id
     )))
 }
+
+/// Split a line of a taxdump `.dmp` file into its trimmed fields
+fn dmp_fields(line: &str) -> Vec<&str> {
+    line.split('|').map(str::trim).collect()
+}
+
+fn taxid_field(fields: &[&str], index: usize) -> Result<i64, String> {
+    let field = fields.get(index).copied().unwrap_or_default();
+    field
+        .parse()
+        .map_err(|_| format!("invalid taxid {:?} in column {}", field, index + 1))
+}
+
+/// Call `handle` with the fields of every line in one of the taxdump files
+fn read_dmp(
+    dir: &Path,
+    filename: &str,
+    mut handle: impl FnMut(&[&str]) -> Result<(), String>,
+) -> Result<(), MibigError> {
+    let path = dir.join(filename);
+    let file = File::open(&path).map_err(|e| {
+        MibigError::InvalidInput(format!("failed to open {}: {}", path.display(), e))
+    })?;
+    let reader = BufReader::new(file);
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        handle(&dmp_fields(&line)).map_err(|e| {
+            MibigError::InvalidInput(format!("{} line {}: {}", path.display(), number + 1, e))
+        })?;
+    }
+    Ok(())
+}
+
+fn rank_for(rank: &str) -> Option<TaxonRank> {
+    match rank {
+        // Newer taxdumps call the top rank "domain"
+        "domain" => Some(TaxonRank::Superkingdom),
+        _ => rank.parse().ok(),
+    }
+}
+
+/// Build a taxon cache for the given taxids from the `nodes.dmp`, `names.dmp` and
+/// `merged.dmp` files of an NCBI taxdump.
+///
+/// Merged taxids are recorded as deprecated IDs, like in caches built by `mibig-taxa`.
+/// Taxids the taxdump doesn't know are left out of the cache.
+pub fn cache_from_taxdump(dir: &Path, taxids: &HashSet<i64>) -> Result<TaxonCache, MibigError> {
+    let mut cache = TaxonCache::new();
+
+    read_dmp(dir, "merged.dmp", |fields| {
+        let old_id = taxid_field(fields, 0)?;
+        if taxids.contains(&old_id) {
+            cache.deprecated_ids.insert(old_id, taxid_field(fields, 1)?);
+        }
+        Ok(())
+    })?;
+
+    let current: HashSet<i64> = taxids
+        .iter()
+        .map(|id| *cache.deprecated_ids.get(id).unwrap_or(id))
+        .collect();
+
+    let mut nodes: HashMap<i64, (i64, Option<TaxonRank>)> = HashMap::new();
+    read_dmp(dir, "nodes.dmp", |fields| {
+        let rank = fields.get(2).copied().unwrap_or_default();
+        nodes.insert(
+            taxid_field(fields, 0)?,
+            (taxid_field(fields, 1)?, rank_for(rank)),
+        );
+        Ok(())
+    })?;
+
+    // Walk up from every taxid to the root, noting the ancestors at the ranks MIBiG uses
+    let mut lineages: HashMap<i64, Vec<(TaxonRank, i64)>> = HashMap::new();
+    for &taxid in &current {
+        let mut lineage = Vec::new();
+        let mut node_id = taxid;
+        while let Some(&(parent_id, rank)) = nodes.get(&node_id) {
+            if let Some(rank) = rank {
+                lineage.push((rank, node_id));
+            }
+            if parent_id == node_id {
+                lineages.insert(taxid, lineage);
+                break;
+            }
+            node_id = parent_id;
+        }
+    }
+    drop(nodes);
+
+    let wanted: HashSet<i64> = lineages
+        .iter()
+        .flat_map(|(taxid, lineage)| {
+            std::iter::once(*taxid).chain(lineage.iter().map(|(_, id)| *id))
+        })
+        .collect();
+    let mut names: HashMap<i64, String> = HashMap::new();
+    read_dmp(dir, "names.dmp", |fields| {
+        let taxid = taxid_field(fields, 0)?;
+        if fields.get(3) == Some(&"scientific name") && wanted.contains(&taxid) {
+            names.insert(
+                taxid,
+                fields.get(1).copied().unwrap_or_default().to_string(),
+            );
+        }
+        Ok(())
+    })?;
+
+    for (taxid, lineage) in lineages {
+        let at_rank = |rank: TaxonRank| {
+            lineage
+                .iter()
+                .find(|(r, _)| *r == rank)
+                .and_then(|(_, id)| names.get(id))
+                .cloned()
+                .unwrap_or_else(|| "Unknown".to_string())
+        };
+        let species = at_rank(TaxonRank::Species);
+        let entry = NcbiTaxEntry {
+            tax_id: taxid,
+            name: names
+                .get(&taxid)
+                .cloned()
+                .unwrap_or_else(|| "Unknown".to_string()),
+            // Like mibig-taxa, only keep the epithet of the species name
+            species: species
+                .split_whitespace()
+                .next_back()
+                .unwrap_or_default()
+                .to_string(),
+            genus: at_rank(TaxonRank::Genus),
+            family: at_rank(TaxonRank::Family),
+            order: at_rank(TaxonRank::Order),
+            class: at_rank(TaxonRank::Class),
+            phylum: at_rank(TaxonRank::Phylum),
+            kingdom: at_rank(TaxonRank::Kingdom),
+            superkingdom: at_rank(TaxonRank::Superkingdom),
+        };
+        cache.mappings.insert(taxid, entry);
+    }
+
+    Ok(cache)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// A scratch directory with the given taxdump files, one `.dmp` line per row of fields
+    fn taxdump(name: &str, files: &[(&str, &[&[&str]])]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mibig-taxdump-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        for (filename, rows) in files {
+            let content: String = rows
+                .iter()
+                .map(|fields| format!("{}\t|\n", fields.join("\t|\t")))
+                .collect();
+            fs::write(dir.join(filename), content).unwrap();
+        }
+        dir
+    }
+
+    fn streptomyces_taxdump(name: &str) -> PathBuf {
+        taxdump(
+            name,
+            &[
+                (
+                    "nodes.dmp",
+                    &[
+                        &["1", "1", "no rank"],
+                        &["2", "1", "domain"],
+                        &["201174", "2", "phylum"],
+                        &["1760", "201174", "class"],
+                        // No order between the class and the family
+                        &["2062", "1760", "family"],
+                        &["1883", "2062", "genus"],
+                        &["1909", "1883", "species"],
+                    ],
+                ),
+                (
+                    "names.dmp",
+                    &[
+                        &["1", "root", "", "scientific name"],
+                        &["2", "Bacteria", "Bacteria <bacteria>", "scientific name"],
+                        &["201174", "Actinomycetota", "", "scientific name"],
+                        &["1760", "Actinomycetes", "", "scientific name"],
+                        &["2062", "Streptomycetaceae", "", "scientific name"],
+                        &["1883", "Streptomyces", "", "scientific name"],
+                        &["1909", "Streptomyces roseosporus", "", "scientific name"],
+                        &["1909", "S. roseosporus", "", "synonym"],
+                    ],
+                ),
+                ("merged.dmp", &[&["100", "1909"], &["200", "1883"]]),
+            ],
+        )
+    }
+
+    #[test]
+    fn dmp_fields_are_split_and_trimmed() {
+        assert_eq!(
+            dmp_fields("1909\t|\t1883\t|\tspecies\t|"),
+            vec!["1909", "1883", "species", ""]
+        );
+        assert_eq!(
+            dmp_fields("2\t|\tBacteria\t|\tBacteria <bacteria>\t|\tscientific name\t|"),
+            vec![
+                "2",
+                "Bacteria",
+                "Bacteria <bacteria>",
+                "scientific name",
+                ""
+            ]
+        );
+    }
+
+    #[test]
+    fn rank_for_maps_domain_to_superkingdom() {
+        assert_eq!(rank_for("domain"), Some(TaxonRank::Superkingdom));
+        assert_eq!(rank_for("superkingdom"), Some(TaxonRank::Superkingdom));
+        assert_eq!(rank_for("order"), Some(TaxonRank::Order));
+        assert_eq!(rank_for("no rank"), None);
+        assert_eq!(rank_for("clade"), None);
+    }
+
+    #[test]
+    fn cache_from_taxdump_builds_lineages() {
+        let dir = streptomyces_taxdump("lineages");
+        let taxids: HashSet<i64> = [1909].iter().copied().collect();
+        let cache = cache_from_taxdump(&dir, &taxids).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let entry = entry_for_taxid(1909, &cache).unwrap();
+        assert_eq!(entry.name, "Streptomyces roseosporus");
+        assert_eq!(entry.superkingdom, "Bacteria");
+        assert_eq!(entry.kingdom, "Unknown");
+        assert_eq!(entry.phylum, "Actinomycetota");
+        assert_eq!(entry.class, "Actinomycetes");
+        assert_eq!(entry.order, "Unknown");
+        assert_eq!(entry.family, "Streptomycetaceae");
+        assert_eq!(entry.genus, "Streptomyces");
+        assert_eq!(entry.species, "roseosporus");
+    }
+
+    #[test]
+    fn cache_from_taxdump_follows_merged_taxids() {
+        let dir = streptomyces_taxdump("merged");
+        let taxids: HashSet<i64> = [100].iter().copied().collect();
+        let cache = cache_from_taxdump(&dir, &taxids).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Only merges of the requested taxids are kept
+        assert_eq!(cache.deprecated_ids.get(&100), Some(&1909));
+        assert_eq!(cache.deprecated_ids.get(&200), None);
+        let entry = entry_for_taxid(100, &cache).unwrap();
+        assert_eq!(entry.tax_id, 1909);
+        assert_eq!(entry.genus, "Streptomyces");
+    }
+
+    #[test]
+    fn cache_from_taxdump_leaves_out_unknown_taxids() {
+        let dir = streptomyces_taxdump("unknown");
+        let taxids: HashSet<i64> = [1909, 999].iter().copied().collect();
+        let cache = cache_from_taxdump(&dir, &taxids).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(cache.mappings.len(), 1);
+        assert!(entry_for_taxid(999, &cache).is_err());
+    }
+}
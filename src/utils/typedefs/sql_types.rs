@@ -1,3 +1,15 @@
 #[derive(QueryId, SqlType)]
 #[postgres(type_name = "citext")]
 pub struct Citext;
+
+#[derive(QueryId, SqlType)]
+#[postgres(type_name = "tsvector")]
+pub struct Tsvector;
+
+#[derive(QueryId, SqlType)]
+#[postgres(type_name = "tsquery")]
+pub struct Tsquery;
+
+#[derive(QueryId, SqlType)]
+#[postgres(type_name = "regconfig")]
+pub struct Regconfig;
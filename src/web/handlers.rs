@@ -1,16 +1,44 @@
+pub mod compounds;
+pub mod entry;
+pub mod repository;
+pub mod requests;
+pub mod search;
+pub mod taxonomy;
+pub mod types;
 pub mod user;
 
+use rocket::serde::json::Json;
+
+use crate::errors::MibigError;
+use crate::models::db::entries::EntryStatus;
+use crate::models::db::roles::CuratorUser;
+use crate::models::db::stats::RepositoryStats;
+use crate::DBPool;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 500;
+
+/// Turn optional offset/limit query parameters into sane pagination values
+pub fn pagination(offset: Option<i64>, limit: Option<i64>) -> (i64, i64) {
+    (
+        offset.unwrap_or(0).max(0),
+        limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+    )
+}
+
 #[get("/version")]
 pub fn version() -> &'static str {
     "version"
 }
 
 #[get("/stats")]
-pub fn stats() -> &'static str {
-    "stats"
-}
-
-#[get("/repository")]
-pub fn repository() -> &'static str {
-    "repository"
+pub async fn stats(
+    conn: DBPool,
+    curator: Option<CuratorUser>,
+) -> Result<Json<RepositoryStats>, MibigError> {
+    let visible = EntryStatus::visible(curator.is_some());
+    let stats = conn
+        .run(move |c| RepositoryStats::collect(&visible, c))
+        .await?;
+    Ok(Json(stats))
 }
@@ -0,0 +1,44 @@
+use rocket::serde::json::Json;
+use serde::Serialize;
+
+use crate::errors::MibigError;
+use crate::models::db::compounds::{Compound, CompoundSearch};
+use crate::models::db::entries::EntryStatus;
+use crate::models::db::roles::CuratorUser;
+use crate::web::handlers::pagination;
+use crate::DBPool;
+
+#[derive(Serialize)]
+pub struct CompoundPage {
+    pub total: i64,
+    pub offset: i64,
+    pub limit: i64,
+    pub compounds: Vec<Compound>,
+}
+
+#[get("/compounds?<offset>&<limit>&<search..>")]
+pub async fn search(
+    conn: DBPool,
+    curator: Option<CuratorUser>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    search: CompoundSearch,
+) -> Result<Json<CompoundPage>, MibigError> {
+    let (offset, limit) = pagination(offset, limit);
+    let visible = EntryStatus::visible(curator.is_some());
+
+    let page = conn
+        .run(move |c| {
+            let total = Compound::search_count(&search, &visible, c)?;
+            let compounds = Compound::search(&search, &visible, offset, limit, c)?;
+            Ok::<_, MibigError>(CompoundPage {
+                total,
+                offset,
+                limit,
+                compounds,
+            })
+        })
+        .await?;
+
+    Ok(Json(page))
+}
@@ -0,0 +1,54 @@
+use rocket::serde::json::Json;
+use serde_json::Value;
+
+use crate::errors::MibigError;
+use crate::models::db::entries::{Entry, EntryStatus};
+use crate::models::db::entry_revisions::{EntryRevision, RevisionSummary};
+use crate::models::db::roles::CuratorUser;
+use crate::DBPool;
+
+#[get("/entry/<accession>")]
+pub async fn get(
+    conn: DBPool,
+    curator: Option<CuratorUser>,
+    accession: String,
+) -> Result<Json<Value>, MibigError> {
+    let visible = EntryStatus::visible(curator.is_some());
+    let data = conn
+        .run(move |c| Entry::get_data(accession, &visible, c))
+        .await?;
+    Ok(Json(data))
+}
+
+#[get("/entry/<accession>/revisions")]
+pub async fn revisions(
+    conn: DBPool,
+    curator: Option<CuratorUser>,
+    accession: String,
+) -> Result<Json<Vec<RevisionSummary>>, MibigError> {
+    let visible = EntryStatus::visible(curator.is_some());
+    let revisions = conn
+        .run(move |c| {
+            Entry::check_visible(&accession, &visible, c)?;
+            EntryRevision::list_for_entry(&accession, c)
+        })
+        .await?;
+    Ok(Json(revisions))
+}
+
+#[get("/entry/<accession>/revisions/<revision>")]
+pub async fn revision(
+    conn: DBPool,
+    curator: Option<CuratorUser>,
+    accession: String,
+    revision: i32,
+) -> Result<Json<Value>, MibigError> {
+    let visible = EntryStatus::visible(curator.is_some());
+    let revision = conn
+        .run(move |c| {
+            Entry::check_visible(&accession, &visible, c)?;
+            EntryRevision::show(&accession, revision, c)
+        })
+        .await?;
+    Ok(Json(revision.data))
+}
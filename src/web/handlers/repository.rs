@@ -0,0 +1,57 @@
+use rocket::serde::json::Json;
+use serde::Serialize;
+
+use crate::errors::MibigError;
+use crate::models::db::entries::{Entry, EntrySort, EntryStatus, EntrySummary};
+use crate::models::db::roles::CuratorUser;
+use crate::models::query::Query;
+use crate::web::handlers::pagination;
+use crate::DBPool;
+
+#[derive(Serialize)]
+pub struct RepositoryPage {
+    pub total: i64,
+    pub offset: i64,
+    pub limit: i64,
+    pub entries: Vec<EntrySummary>,
+}
+
+#[get("/repository?<offset>&<limit>&<sort>&<desc>&<query>")]
+pub async fn list(
+    conn: DBPool,
+    curator: Option<CuratorUser>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    sort: Option<EntrySort>,
+    desc: Option<bool>,
+    query: Option<String>,
+) -> Result<Json<RepositoryPage>, MibigError> {
+    let (offset, limit) = pagination(offset, limit);
+    let sort = sort.unwrap_or(EntrySort::Accession);
+    let descending = desc.unwrap_or(false);
+    let visible = EntryStatus::visible(curator.is_some());
+    let query = query.map(|q| q.parse::<Query>()).transpose()?;
+
+    let page = conn
+        .run(move |c| {
+            let total = Entry::count(query.as_ref(), &visible, c)?;
+            let entries = Entry::list_summaries(
+                sort,
+                descending,
+                query.as_ref(),
+                &visible,
+                offset,
+                limit,
+                c,
+            )?;
+            Ok::<_, MibigError>(RepositoryPage {
+                total,
+                offset,
+                limit,
+                entries,
+            })
+        })
+        .await?;
+
+    Ok(Json(page))
+}
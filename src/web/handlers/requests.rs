@@ -0,0 +1,161 @@
+use diesel::result::Error as DieselError;
+use rocket::serde::json::Json;
+use serde::Deserialize;
+
+use crate::errors::MibigError;
+use crate::models::db::roles::{CuratorUser, SubmitterUser};
+use crate::models::db::submission_requests::{SubmissionRequest, STATUS_PENDING};
+use crate::DBPool;
+
+#[derive(Deserialize)]
+pub struct NewRequestData {
+    pub compounds: Vec<String>,
+    pub accession: String,
+    pub start_nt: Option<i32>,
+    pub end_nt: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct RejectData {
+    pub reason: String,
+}
+
+#[post("/requests", format = "json", data = "<request_data>")]
+pub async fn create(
+    conn: DBPool,
+    submitter: SubmitterUser,
+    request_data: Json<NewRequestData>,
+) -> Result<Json<SubmissionRequest>, MibigError> {
+    let NewRequestData {
+        compounds,
+        accession,
+        start_nt,
+        end_nt,
+    } = request_data.into_inner();
+
+    if compounds.iter().all(|c| c.trim().is_empty()) {
+        return Err(MibigError::InvalidInput(
+            "need at least one compound name".to_string(),
+        ));
+    }
+    if accession.trim().is_empty() {
+        return Err(MibigError::InvalidInput("need an accession".to_string()));
+    }
+    if let (Some(start), Some(end)) = (start_nt, end_nt) {
+        if start >= end {
+            return Err(MibigError::InvalidInput(
+                "start coordinate must be before end coordinate".to_string(),
+            ));
+        }
+    }
+
+    let req = conn
+        .run(move |c| {
+            SubmissionRequest::new(
+                submitter.0.user_id,
+                compounds,
+                accession,
+                start_nt,
+                end_nt,
+                c,
+            )
+        })
+        .await?;
+    Ok(Json(req))
+}
+
+#[get("/requests")]
+pub async fn list(
+    conn: DBPool,
+    submitter: SubmitterUser,
+) -> Result<Json<Vec<SubmissionRequest>>, MibigError> {
+    let requests = conn
+        .run(move |c| SubmissionRequest::list_for_user(&submitter.0.user_id, c))
+        .await?;
+    Ok(Json(requests))
+}
+
+/// Load a request, pretending requests filed by other users don't exist
+fn own_request(
+    id: i64,
+    user_id: &str,
+    conn: &diesel::PgConnection,
+) -> Result<SubmissionRequest, MibigError> {
+    let req = SubmissionRequest::show(id, conn)?;
+    if req.user_id != user_id {
+        return Err(MibigError::DatabaseError(DieselError::NotFound));
+    }
+    Ok(req)
+}
+
+#[get("/requests/<id>")]
+pub async fn get(
+    conn: DBPool,
+    submitter: SubmitterUser,
+    id: i64,
+) -> Result<Json<SubmissionRequest>, MibigError> {
+    let req = conn
+        .run(move |c| own_request(id, &submitter.0.user_id, c))
+        .await?;
+    Ok(Json(req))
+}
+
+#[delete("/requests/<id>")]
+pub async fn delete(conn: DBPool, submitter: SubmitterUser, id: i64) -> Result<(), MibigError> {
+    conn.run(move |c| {
+        let req = own_request(id, &submitter.0.user_id, c)?;
+        if req.status != STATUS_PENDING {
+            return Err(MibigError::InvalidInput(format!(
+                "request {} is already {}",
+                id, req.status
+            )));
+        }
+        SubmissionRequest::delete(id, c)
+    })
+    .await?;
+    Ok(())
+}
+
+#[get("/requests/pending")]
+pub async fn pending(
+    conn: DBPool,
+    _curator: CuratorUser,
+) -> Result<Json<Vec<SubmissionRequest>>, MibigError> {
+    let requests = conn.run(|c| SubmissionRequest::list_pending(c)).await?;
+    Ok(Json(requests))
+}
+
+#[put("/requests/<id>/approve")]
+pub async fn approve(
+    conn: DBPool,
+    curator: CuratorUser,
+    id: i64,
+) -> Result<Json<SubmissionRequest>, MibigError> {
+    let req = conn
+        .run(move |c| {
+            SubmissionRequest::approve(id, &curator.0.user_id, c)?;
+            SubmissionRequest::show(id, c)
+        })
+        .await?;
+    Ok(Json(req))
+}
+
+#[put("/requests/<id>/reject", format = "json", data = "<reject_data>")]
+pub async fn reject(
+    conn: DBPool,
+    curator: CuratorUser,
+    id: i64,
+    reject_data: Json<RejectData>,
+) -> Result<Json<SubmissionRequest>, MibigError> {
+    let reason = reject_data.into_inner().reason;
+    if reason.trim().is_empty() {
+        return Err(MibigError::InvalidInput(
+            "need a reason for rejecting a request".to_string(),
+        ));
+    }
+
+    let req = conn
+        .run(move |c| SubmissionRequest::reject(id, &curator.0.user_id, &reason, c))
+        .await?;
+    Ok(Json(req))
+}
@@ -0,0 +1,48 @@
+use rocket::serde::json::Json;
+use serde::Serialize;
+
+use crate::errors::MibigError;
+use crate::models::db::entries::{EntryStatus, EntrySummary};
+use crate::models::db::entry_search::{EntrySearch, SearchFacets};
+use crate::models::db::roles::CuratorUser;
+use crate::models::db::taxa::TaxonRank;
+use crate::web::handlers::pagination;
+use crate::DBPool;
+
+#[derive(Serialize)]
+pub struct SearchPage {
+    pub total: i64,
+    pub offset: i64,
+    pub limit: i64,
+    pub entries: Vec<EntrySummary>,
+    pub facets: SearchFacets,
+}
+
+#[get("/search?<q>&<rank>&<offset>&<limit>")]
+pub async fn search(
+    conn: DBPool,
+    curator: Option<CuratorUser>,
+    q: String,
+    rank: Option<TaxonRank>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Json<SearchPage>, MibigError> {
+    if q.trim().is_empty() {
+        return Err(MibigError::InvalidInput("empty search query".to_string()));
+    }
+    let (offset, limit) = pagination(offset, limit);
+    let rank = rank.unwrap_or(TaxonRank::Phylum);
+    let visible = EntryStatus::visible(curator.is_some());
+
+    let results = conn
+        .run(move |c| EntrySearch::search(&q, rank, &visible, offset, limit, c))
+        .await?;
+
+    Ok(Json(SearchPage {
+        total: results.total,
+        offset,
+        limit,
+        entries: results.entries,
+        facets: results.facets,
+    }))
+}
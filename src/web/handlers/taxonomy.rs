@@ -0,0 +1,69 @@
+use rocket::serde::json::Json;
+
+use crate::errors::MibigError;
+use crate::models::db::entries::{Entry, EntryStatus};
+use crate::models::db::roles::CuratorUser;
+use crate::models::db::taxa::{Taxon, TaxonNode, TaxonRank};
+use crate::web::handlers::pagination;
+use crate::web::handlers::repository::RepositoryPage;
+use crate::DBPool;
+
+#[get("/taxonomy?<depth>")]
+pub async fn tree(
+    conn: DBPool,
+    curator: Option<CuratorUser>,
+    depth: Option<TaxonRank>,
+) -> Result<Json<Vec<TaxonNode>>, MibigError> {
+    let depth = depth.unwrap_or(TaxonRank::Genus);
+    let visible = EntryStatus::visible(curator.is_some());
+
+    let tree = conn.run(move |c| Taxon::tree(depth, &visible, c)).await?;
+
+    Ok(Json(tree))
+}
+
+#[get("/taxonomy/<rank>/<name>?<depth>")]
+pub async fn subtree(
+    conn: DBPool,
+    curator: Option<CuratorUser>,
+    rank: String,
+    name: String,
+    depth: Option<TaxonRank>,
+) -> Result<Json<TaxonNode>, MibigError> {
+    let rank: TaxonRank = rank.parse()?;
+    let depth = depth.unwrap_or(TaxonRank::Genus);
+    let visible = EntryStatus::visible(curator.is_some());
+
+    let node = conn
+        .run(move |c| Taxon::subtree(rank, &name, depth, &visible, c))
+        .await?;
+
+    Ok(Json(node))
+}
+
+#[get("/taxonomy/taxid/<taxid>/entries?<offset>&<limit>")]
+pub async fn entries(
+    conn: DBPool,
+    curator: Option<CuratorUser>,
+    taxid: i64,
+    offset: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Json<RepositoryPage>, MibigError> {
+    let (offset, limit) = pagination(offset, limit);
+    let visible = EntryStatus::visible(curator.is_some());
+
+    let page = conn
+        .run(move |c| {
+            let ids = Taxon::entry_ids(taxid, &visible, c)?;
+            let entries = Entry::list_summaries_in(&ids, &visible, offset, limit, c)?;
+            Ok::<_, MibigError>(RepositoryPage {
+                total: ids.len() as i64,
+                offset,
+                limit,
+                entries,
+            })
+        })
+        .await?;
+
+    Ok(Json(page))
+}
@@ -0,0 +1,43 @@
+use rocket::serde::json::Json;
+
+use crate::errors::MibigError;
+use crate::models::db::bgc_types::{BgcType, BgcTypeNode};
+use crate::models::db::entries::{Entry, EntryStatus};
+use crate::models::db::roles::CuratorUser;
+use crate::web::handlers::pagination;
+use crate::web::handlers::repository::RepositoryPage;
+use crate::DBPool;
+
+#[get("/types")]
+pub async fn list(conn: DBPool) -> Result<Json<Vec<BgcTypeNode>>, MibigError> {
+    let types = conn.run(|c| BgcType::all(c)).await?;
+    Ok(Json(BgcType::tree(&types)))
+}
+
+#[get("/types/<term>/entries?<offset>&<limit>")]
+pub async fn entries(
+    conn: DBPool,
+    curator: Option<CuratorUser>,
+    term: String,
+    offset: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Json<RepositoryPage>, MibigError> {
+    let (offset, limit) = pagination(offset, limit);
+    let visible = EntryStatus::visible(curator.is_some());
+
+    let page = conn
+        .run(move |c| {
+            let bgc_type = BgcType::by_term(&term, c)?;
+            let ids = bgc_type.entry_ids(&visible, c)?;
+            let entries = Entry::list_summaries_in(&ids, &visible, offset, limit, c)?;
+            Ok::<_, MibigError>(RepositoryPage {
+                total: ids.len() as i64,
+                offset,
+                limit,
+                entries,
+            })
+        })
+        .await?;
+
+    Ok(Json(page))
+}
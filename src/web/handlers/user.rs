@@ -1,12 +1,19 @@
+use std::sync::Arc;
+
 use chrono::Duration;
-use diesel::result::Error as DieselError;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel::Connection;
 use rocket::http::{Cookie, CookieJar};
 use rocket::serde::json::Json;
+use rocket::State;
 use serde::{Deserialize, Serialize};
 
 use crate::errors::MibigError;
+use crate::models::db::roles::{AdminUser, Role, SUBMITTER};
 use crate::models::db::submitters::Submitter;
-use crate::models::db::tokens::Token;
+use crate::models::db::tokens::{Token, TokenScope};
+use crate::utils::mailer::Mailer;
+use crate::utils::{generate_password, generate_uid};
 use crate::DBPool;
 
 #[derive(Deserialize)]
@@ -46,14 +53,10 @@ pub async fn login(
                 Err(e) => return Err(e),
             }
             let valid_auth = user.check_password(user_data.password.clone().to_string())?;
-            if !valid_auth {
+            if !valid_auth || !user.active {
                 return Err(MibigError::Unauthorised);
             }
-            let token = Token::new(
-                user.user_id,
-                Duration::days(1),
-                "Authentication".to_string(),
-            )?;
+            let token = Token::new(user.user_id, Duration::days(1), TokenScope::Authentication)?;
             Token::insert(&token, &c)?;
             Ok(token)
         })
@@ -72,17 +75,321 @@ pub async fn login(
 }
 
 #[post("/user/logout")]
-pub fn logout(cookies: &CookieJar<'_>) -> &'static str {
+pub async fn logout(
+    conn: DBPool,
+    cookies: &CookieJar<'_>,
+    token: Option<Token>,
+) -> Result<&'static str, MibigError> {
     cookies.remove_private(Cookie::named("token"));
-    "logout"
+    if let Some(token) = token {
+        conn.run(move |c| Token::delete(token.hash, c)).await?;
+    }
+    Ok("logout")
+}
+
+#[post("/user/logout/all")]
+pub async fn logout_all(
+    conn: DBPool,
+    cookies: &CookieJar<'_>,
+    token: Token,
+) -> Result<&'static str, MibigError> {
+    cookies.remove_private(Cookie::named("token"));
+    conn.run(move |c| Token::delete_all_for_user(TokenScope::Authentication, token.user_id, c))
+        .await?;
+    Ok("logout")
+}
+
+#[derive(Deserialize)]
+pub struct UserRegistrationData {
+    pub email: String,
+    pub password: String,
+    pub name: String,
+    pub call_name: Option<String>,
+    pub institution: Option<String>,
+    #[serde(default)]
+    pub is_public: bool,
+    #[serde(default)]
+    pub gdpr_consent: bool,
+}
+
+#[derive(Deserialize)]
+pub struct UserActivationData {
+    pub token: String,
+}
+
+fn already_registered() -> MibigError {
+    MibigError::InvalidInput("email address already registered".to_string())
+}
+
+#[post("/user/register", format = "json", data = "<user_data>")]
+pub async fn register(
+    conn: DBPool,
+    mailer: &State<Arc<dyn Mailer>>,
+    user_data: Json<UserRegistrationData>,
+) -> Result<Json<UserInfo>, MibigError> {
+    if !user_data.email.contains('@') {
+        return Err(MibigError::InvalidInput(
+            "invalid email address".to_string(),
+        ));
+    }
+    if user_data.password.is_empty() || user_data.name.is_empty() {
+        return Err(MibigError::InvalidInput(
+            "name and password are required".to_string(),
+        ));
+    }
+    if !user_data.gdpr_consent {
+        return Err(MibigError::InvalidInput(
+            "consent to store the user data is required".to_string(),
+        ));
+    }
+
+    let mailer = Arc::clone(mailer.inner());
+    let user = conn
+        .run(move |c| {
+            match Submitter::get_id_by_email(user_data.email.clone().into(), c) {
+                Ok(_) => return Err(already_registered()),
+                Err(MibigError::DatabaseError(DieselError::NotFound)) => {}
+                Err(e) => return Err(e),
+            }
+
+            let user_data = user_data.into_inner();
+            let submitter = Submitter {
+                user_id: generate_uid(15)?,
+                email: user_data.email.into(),
+                name: Some(user_data.name),
+                call_name: user_data.call_name,
+                institution: user_data.institution,
+                password_hash: Some(generate_password(user_data.password)?),
+                is_public: user_data.is_public,
+                gdpr_consent: user_data.gdpr_consent,
+                active: false,
+                version: 0,
+            };
+            let user_id = submitter.user_id.clone();
+
+            // Send the activation mail before committing, so a user that can't be reached
+            // isn't left behind unable to register again
+            c.transaction::<_, MibigError, _>(|| {
+                // A concurrent registration can still get in between the check and the insert
+                Submitter::insert(submitter, c).map_err(|e| match e {
+                    MibigError::DatabaseError(DieselError::DatabaseError(
+                        DatabaseErrorKind::UniqueViolation,
+                        _,
+                    )) => already_registered(),
+                    e => e,
+                })?;
+                Role::set_for_user(&user_id, &[SUBMITTER.to_string()], c)?;
+                let token = Token::new(user_id.clone(), Duration::days(3), TokenScope::Activation)?;
+                Token::insert(&token, c)?;
+                let user = Submitter::show(&user_id, c)?;
+                mailer.send(
+                    &user.email,
+                    "Activate your MIBiG account",
+                    &format!(
+                        "Welcome to MIBiG, {name}!\n\nYour activation token is {token}, it is valid until {expiry}.",
+                        name = user.call_name.as_ref().or(user.name.as_ref()).unwrap_or(&user.email),
+                        token = token.hash,
+                        expiry = token.expiry.format("%+"),
+                    ),
+                )?;
+                Ok(user)
+            })
+        })
+        .await?;
+
+    Ok(Json(UserInfo::new(user, vec![SUBMITTER.to_string()])))
+}
+
+#[put("/user/activate", format = "json", data = "<activation_data>")]
+pub async fn activate(
+    conn: DBPool,
+    activation_data: Json<UserActivationData>,
+) -> Result<Json<UserInfo>, MibigError> {
+    let user = conn
+        .run(move |c| {
+            let token =
+                match Token::show_valid(activation_data.token.clone(), TokenScope::Activation, c) {
+                    Ok(val) => val,
+                    Err(MibigError::DatabaseError(DieselError::NotFound)) => {
+                        return Err(MibigError::Unauthorised);
+                    }
+                    Err(e) => return Err(e),
+                };
+
+            c.transaction::<_, MibigError, _>(|| {
+                let mut user = Submitter::show(&token.user_id, c)?;
+                user.active = true;
+                Submitter::update(&token.user_id, user, c)?;
+                Token::delete_all_for_user(TokenScope::Activation, token.user_id.clone(), c)?;
+                let user = Submitter::show(&token.user_id, c)?;
+                let roles = Role::names_for_user(&user.user_id, c)?;
+                Ok(UserInfo::new(user, roles))
+            })
+        })
+        .await?;
+
+    Ok(Json(user))
+}
+
+#[derive(Deserialize)]
+pub struct PasswordResetRequestData {
+    pub email: String,
 }
 
-#[post("/user/register")]
-pub fn register() -> &'static str {
-    "register"
+#[derive(Deserialize)]
+pub struct PasswordResetData {
+    pub token: String,
+    pub password: String,
+}
+
+#[post("/user/password-reset", format = "json", data = "<reset_data>")]
+pub async fn request_password_reset(
+    conn: DBPool,
+    mailer: &State<Arc<dyn Mailer>>,
+    reset_data: Json<PasswordResetRequestData>,
+) -> Result<&'static str, MibigError> {
+    // Answer the same way whether or not the address is known and whether or not the mail
+    // went out, so this can't be used to find out who has an account
+    let mailer = Arc::clone(mailer.inner());
+    conn.run(move |c| {
+        let user = match Submitter::get_id_by_email(reset_data.email.clone().into(), c) {
+            Ok(id) => Submitter::show(&id, c)?,
+            Err(MibigError::DatabaseError(DieselError::NotFound)) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if !user.active {
+            return Ok(());
+        }
+
+        // Send the mail before committing, so no token is left behind that nobody received
+        let res = c.transaction::<_, MibigError, _>(|| {
+            let token = Token::new(
+                user.user_id.clone(),
+                Duration::hours(1),
+                TokenScope::PasswordReset,
+            )?;
+            Token::insert(&token, c)?;
+            if let Err(e) = mailer.send(
+                &user.email,
+                "Reset your MIBiG password",
+                &format!(
+                    "Your password reset token is {token}, it is valid until {expiry}.\n\nIf you did not request a password reset, you can ignore this message.",
+                    token = token.hash,
+                    expiry = token.expiry.format("%+"),
+                ),
+            ) {
+                eprintln!("Failed to send password reset mail to {}: {}", user.email, e);
+                return Err(DieselError::RollbackTransaction.into());
+            }
+            Ok(())
+        });
+        match res {
+            Err(MibigError::DatabaseError(DieselError::RollbackTransaction)) => Ok(()),
+            res => res,
+        }
+    })
+    .await?;
+
+    Ok("password reset requested")
 }
 
-#[put("/user/activate")]
-pub fn activate() -> &'static str {
-    "activate"
+#[put("/user/password-reset", format = "json", data = "<reset_data>")]
+pub async fn reset_password(
+    conn: DBPool,
+    reset_data: Json<PasswordResetData>,
+) -> Result<&'static str, MibigError> {
+    if reset_data.password.is_empty() {
+        return Err(MibigError::InvalidInput("password is required".to_string()));
+    }
+
+    conn.run(move |c| {
+        let PasswordResetData { token, password } = reset_data.into_inner();
+        let token = match Token::show_valid(token, TokenScope::PasswordReset, c) {
+            Ok(val) => val,
+            Err(MibigError::DatabaseError(DieselError::NotFound)) => {
+                return Err(MibigError::Unauthorised);
+            }
+            Err(e) => return Err(e),
+        };
+
+        c.transaction::<_, MibigError, _>(|| {
+            let mut user = Submitter::show(&token.user_id, c)?;
+            user.password_hash = Some(generate_password(password)?);
+            Submitter::update(&token.user_id, user, c)?;
+            // Existing sessions were opened with the old password
+            Token::delete_all_for_user(TokenScope::PasswordReset, token.user_id.clone(), c)?;
+            Token::delete_all_for_user(TokenScope::Authentication, token.user_id.clone(), c)?;
+            Ok(())
+        })
+    })
+    .await?;
+
+    Ok("password updated")
+}
+
+#[derive(Serialize)]
+pub struct UserInfo {
+    pub user_id: String,
+    pub email: String,
+    pub name: Option<String>,
+    pub call_name: Option<String>,
+    pub institution: Option<String>,
+    pub is_public: bool,
+    pub gdpr_consent: bool,
+    pub active: bool,
+    pub roles: Vec<String>,
+}
+
+impl UserInfo {
+    fn new(user: Submitter, roles: Vec<String>) -> UserInfo {
+        UserInfo {
+            user_id: user.user_id,
+            email: user.email.to_string(),
+            name: user.name,
+            call_name: user.call_name,
+            institution: user.institution,
+            is_public: user.is_public,
+            gdpr_consent: user.gdpr_consent,
+            active: user.active,
+            roles,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UserRolesData {
+    pub roles: Vec<String>,
+}
+
+#[get("/users")]
+pub async fn list(conn: DBPool, _admin: AdminUser) -> Result<Json<Vec<UserInfo>>, MibigError> {
+    let users = conn
+        .run(|c| {
+            let mut users: Vec<UserInfo> = Vec::new();
+            for user in Submitter::all(c)? {
+                let roles = Role::names_for_user(&user.user_id, c)?;
+                users.push(UserInfo::new(user, roles));
+            }
+            Ok::<_, MibigError>(users)
+        })
+        .await?;
+    Ok(Json(users))
+}
+
+#[put("/user/<user_id>/roles", format = "json", data = "<roles_data>")]
+pub async fn set_roles(
+    conn: DBPool,
+    _admin: AdminUser,
+    user_id: String,
+    roles_data: Json<UserRolesData>,
+) -> Result<Json<UserInfo>, MibigError> {
+    let user = conn
+        .run(move |c| {
+            let user = Submitter::show(&user_id, c)?;
+            Role::set_for_user(&user.user_id, &roles_data.roles, c)?;
+            let roles = Role::names_for_user(&user.user_id, c)?;
+            Ok::<_, MibigError>(UserInfo::new(user, roles))
+        })
+        .await?;
+    Ok(Json(user))
 }
@@ -4,10 +4,32 @@ pub fn get_routes() -> std::vec::Vec<rocket::Route> {
     routes![
         handlers::version,
         handlers::stats,
-        handlers::repository,
+        handlers::repository::list,
+        handlers::entry::get,
+        handlers::entry::revisions,
+        handlers::entry::revision,
+        handlers::compounds::search,
+        handlers::search::search,
+        handlers::types::list,
+        handlers::types::entries,
+        handlers::taxonomy::tree,
+        handlers::taxonomy::subtree,
+        handlers::taxonomy::entries,
+        handlers::requests::create,
+        handlers::requests::list,
+        handlers::requests::get,
+        handlers::requests::delete,
+        handlers::requests::pending,
+        handlers::requests::approve,
+        handlers::requests::reject,
         handlers::user::login,
         handlers::user::logout,
+        handlers::user::logout_all,
         handlers::user::register,
         handlers::user::activate,
+        handlers::user::request_password_reset,
+        handlers::user::reset_password,
+        handlers::user::list,
+        handlers::user::set_roles,
     ]
 }